@@ -1,2 +1,103 @@
+use crate::p5::P5;
+use crate::Key;
+use font_kit::family_name::FamilyName;
+use font_kit::font::Font;
+use font_kit::properties::Properties;
+use font_kit::source::SystemSource;
+
+pub(crate) mod clip;
+pub(crate) mod path;
+pub(crate) mod pixels;
+pub(crate) mod points;
+#[cfg(feature = "raqote")]
 pub(crate) mod raqote;
+pub(crate) mod shape;
+pub(crate) mod style;
+#[cfg(feature = "tiny-skia")]
 pub(crate) mod tiny_skia;
+pub(crate) mod vector;
+
+/// The interface that a [`Sketch`](crate::Sketch) uses to drive a backend. Every backend
+/// implements this alongside [`P5`](crate::P5Trait), which is what allows the same sketch to be
+/// drawn by any backend.
+///
+/// These functions are called by the sketch's event loop, and usually shouldn't be called from a
+/// sketch directly.
+pub trait Backend: P5 + Sized {
+    /// Creates a backend with a blank canvas of the given size.
+    fn new(width: usize, height: usize) -> Self;
+
+    /// Prepares for drawing a new frame, by incrementing the `frame_count` and resetting the
+    /// transformation matrix.
+    fn begin_frame(&mut self);
+
+    /// Finishes a frame that `draw` has just drawn, when the sketch is rendered without a window
+    /// by [`run_headless`](crate::Sketch::run_headless),
+    /// [`save_frames`](crate::Sketch::save_frames) or [`save_gif`](crate::Sketch::save_gif). A
+    /// window keeps drawing frames until it's closed, so this isn't called by
+    /// [`run`](crate::Sketch::run). By default, this does nothing.
+    fn end_frame(&mut self) {}
+
+    /// Updates the keys which are currently held down.
+    fn set_keys(&mut self, keys: Option<Vec<Key>>);
+
+    /// Returns the frame rate that was requested with [`frame_rate`](crate::P5Trait::frame_rate).
+    fn target_frame_rate(&self) -> f32;
+
+    /// Copies the canvas into an image, with colors that are _not_ premultiplied by their alpha.
+    fn to_image(&self) -> image::RgbaImage;
+}
+
+/// Creates an image of the given size from the RGBA components of each pixel, which are _not_
+/// premultiplied by their alpha, row by row.
+pub(crate) fn image_from_pixels<I>(width: u32, height: u32, pixels: I) -> image::RgbaImage
+where
+    I: Iterator<Item = [u8; 4]>,
+{
+    let data = pixels.flatten().collect();
+    image::RgbaImage::from_raw(width, height, data)
+        .expect("There should always be one pixel for each point on the canvas")
+}
+
+/// Saves `image` to `path`. The format is chosen from the extension of `path`, and since JPEG
+/// doesn't support transparency, the alpha channel is dropped when saving to JPEG.
+pub(crate) fn save_image<P: AsRef<std::path::Path>>(
+    image: image::RgbaImage,
+    path: P,
+) -> image::ImageResult<()> {
+    let image = image::DynamicImage::ImageRgba8(image);
+    match image::ImageFormat::from_path(&path)? {
+        image::ImageFormat::Jpeg => image::DynamicImage::ImageRgb8(image.to_rgb8()).save(path),
+        _ => image.save(path),
+    }
+}
+
+/// Loads the system's default sans-serif font, which is used for text until `text_font` is
+/// called.
+pub(crate) fn default_font() -> Font {
+    SystemSource::new()
+        .select_best_match(&[FamilyName::SansSerif], &Properties::default())
+        .expect("Default sans-serif font not found")
+        .load()
+        .expect("Failed to load default sans-serif font")
+}
+
+/// Loads the best match for `family_name` from the system's fonts. The CSS generic family names
+/// (e.g. "serif" or "monospace") are also recognized.
+// TODO: Better error handling here
+pub(crate) fn load_font(family_name: &str) -> Font {
+    let family_name = match family_name {
+        "serif" => FamilyName::Serif,
+        "sans-serif" => FamilyName::SansSerif,
+        "monospace" => FamilyName::Monospace,
+        "cursive" => FamilyName::Cursive,
+        "fantasy" => FamilyName::Fantasy,
+        x => FamilyName::Title(x.to_owned()),
+    };
+
+    SystemSource::new()
+        .select_best_match(&[family_name], &Properties::default())
+        .expect("Invalid font specified")
+        .load()
+        .expect("Failed to load font.")
+}
@@ -0,0 +1,42 @@
+use crate::backend::path::Path;
+use euclid::default::Transform2D;
+
+/// The shapes drawn between [`begin_clip`](crate::P5Trait::begin_clip) and
+/// [`end_clip`](crate::P5Trait::end_clip), which make up the region that drawing is confined to.
+pub(crate) struct Clip {
+    /// The outlines of the shapes, already transformed by the current transformation.
+    pub(crate) paths: Vec<Path>,
+    /// Whether drawing is confined to the outside of the shapes, instead of the inside.
+    pub(crate) invert: bool,
+}
+
+impl Clip {
+    pub(crate) fn new(invert: bool) -> Clip {
+        Clip {
+            paths: Vec::new(),
+            invert,
+        }
+    }
+
+    /// Adds the shape outlined by `path`, after transforming it by `transform`.
+    pub(crate) fn add(&mut self, path: &Path, transform: &Transform2D<f32>) {
+        self.paths.push(path.transform(transform));
+    }
+
+    /// Returns a single path made up of all of the shapes, which covers all of them when it's
+    /// filled using the non-zero winding rule. Where a clockwise and a counter-clockwise shape
+    /// overlap, their windings would cancel out, so shapes going counter-clockwise are reversed
+    /// first. Each shape is reversed as a whole, so holes cut into a shape by going the other way,
+    /// like in the glyphs of text, are kept.
+    pub(crate) fn union(&self) -> Path {
+        let mut union = Path::new();
+        for path in &self.paths {
+            if path.signed_area() < 0. {
+                union.segments.extend(path.reversed().segments);
+            } else {
+                union.segments.extend_from_slice(&path.segments);
+            }
+        }
+        union
+    }
+}
@@ -0,0 +1,497 @@
+use crate::p5::{ArcMode, EllipseMode, RectMode, RectRounding};
+use euclid::default::{Box2D, Point2D, Transform2D};
+use euclid::{point2, vec2, Angle};
+use font_kit::font::Font;
+use font_kit::hinting::HintingOptions;
+use font_kit::outline::OutlineSink;
+use lyon_geom::CubicBezierSegment;
+use pathfinder_geometry::line_segment::LineSegment2F;
+use pathfinder_geometry::vector::Vector2F;
+
+/// A single segment of a [`Path`](crate::backend::path::Path).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Segment {
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadTo(Point2D<f32>, Point2D<f32>),
+    CubicTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    Close,
+}
+
+/// A backend-independent description of the outline of a shape. The shape functions in the `P5`
+/// trait build one of these, and each backend converts it into its own path type for drawing, so
+/// the geometry only has to be written once.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Path {
+    pub(crate) segments: Vec<Segment>,
+}
+
+impl Path {
+    pub(crate) fn new() -> Path {
+        Path::default()
+    }
+
+    pub(crate) fn move_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::MoveTo(point2(x, y)));
+    }
+
+    /// Adds a line to `(x, y)`. If the path is empty, this starts the path at `(x, y)` instead,
+    /// since raqote would draw a line from where the previously drawn path started.
+    pub(crate) fn line_to(&mut self, x: f32, y: f32) {
+        if self.segments.is_empty() {
+            self.move_to(x, y);
+        } else {
+            self.segments.push(Segment::LineTo(point2(x, y)));
+        }
+    }
+
+    pub(crate) fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) {
+        self.segments
+            .push(Segment::QuadTo(point2(cx, cy), point2(x, y)));
+    }
+
+    pub(crate) fn cubic_to(&mut self, cx1: f32, cy1: f32, cx2: f32, cy2: f32, x: f32, y: f32) {
+        self.segments.push(Segment::CubicTo(
+            point2(cx1, cy1),
+            point2(cx2, cy2),
+            point2(x, y),
+        ));
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.segments.push(Segment::Close);
+    }
+
+    /// Returns a copy of this path with every point transformed by `transform`.
+    pub(crate) fn transform(&self, transform: &Transform2D<f32>) -> Path {
+        let t = |p: &Point2D<f32>| transform.transform_point(*p);
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::MoveTo(p) => Segment::MoveTo(t(p)),
+                Segment::LineTo(p) => Segment::LineTo(t(p)),
+                Segment::QuadTo(c, p) => Segment::QuadTo(t(c), t(p)),
+                Segment::CubicTo(c1, c2, p) => Segment::CubicTo(t(c1), t(c2), t(p)),
+                Segment::Close => Segment::Close,
+            })
+            .collect();
+
+        Path { segments }
+    }
+
+    /// Returns the smallest box containing every point of this path, including the control points
+    /// of its curves, so the whole path is inside of it. Returns `None` if the path is empty.
+    #[cfg_attr(not(feature = "raqote"), allow(dead_code))]
+    pub(crate) fn bounds(&self) -> Option<Box2D<f32>> {
+        let points: Vec<Point2D<f32>> = self
+            .segments
+            .iter()
+            .flat_map(|segment| match *segment {
+                Segment::MoveTo(p) | Segment::LineTo(p) => vec![p],
+                Segment::QuadTo(c, p) => vec![c, p],
+                Segment::CubicTo(c1, c2, p) => vec![c1, c2, p],
+                Segment::Close => vec![],
+            })
+            .collect();
+        if points.is_empty() {
+            None
+        } else {
+            Some(Box2D::from_points(points))
+        }
+    }
+
+    /// Returns twice the area enclosed by the path, which is positive if the path mostly goes
+    /// clockwise around the canvas, where the y-axis points down, and negative if it mostly goes
+    /// counter-clockwise. Curves are measured along their control points, which is enough to tell
+    /// which way they go around.
+    pub(crate) fn signed_area(&self) -> f32 {
+        let cross = |a: Point2D<f32>, b: Point2D<f32>| a.x * b.y - a.y * b.x;
+        let mut area = 0.;
+        let (mut start, mut current) = (Point2D::zero(), Point2D::zero());
+        for segment in &self.segments {
+            match *segment {
+                Segment::MoveTo(p) => {
+                    // Filling a path closes each subpath, whether or not it was closed.
+                    area += cross(current, start);
+                    start = p;
+                    current = p;
+                }
+                Segment::Close => {
+                    area += cross(current, start);
+                    current = start;
+                }
+                Segment::LineTo(p) => {
+                    area += cross(current, p);
+                    current = p;
+                }
+                Segment::QuadTo(c, p) => {
+                    area += cross(current, c) + cross(c, p);
+                    current = p;
+                }
+                Segment::CubicTo(c1, c2, p) => {
+                    area += cross(current, c1) + cross(c1, c2) + cross(c2, p);
+                    current = p;
+                }
+            }
+        }
+        area + cross(current, start)
+    }
+
+    /// Returns a copy of this path with each of its subpaths going the opposite way, which covers
+    /// the same area, but with the opposite winding.
+    pub(crate) fn reversed(&self) -> Path {
+        let mut reversed = Path::new();
+        // The segments of the current subpath, each with the point it starts from.
+        let mut subpath = Vec::new();
+        let (mut start, mut current) = (Point2D::zero(), Point2D::zero());
+        for &segment in &self.segments {
+            match segment {
+                Segment::MoveTo(p) => {
+                    reversed.reverse_subpath(&subpath, current, false);
+                    subpath.clear();
+                    start = p;
+                    current = p;
+                }
+                Segment::Close => {
+                    reversed.reverse_subpath(&subpath, current, true);
+                    subpath.clear();
+                    current = start;
+                }
+                Segment::LineTo(p) | Segment::QuadTo(_, p) | Segment::CubicTo(_, _, p) => {
+                    subpath.push((current, segment));
+                    current = p;
+                }
+            }
+        }
+        reversed.reverse_subpath(&subpath, current, false);
+        reversed
+    }
+
+    /// Adds the `subpath` that ends at `end` backwards, as returned by [`Path::reversed`].
+    fn reverse_subpath(
+        &mut self,
+        subpath: &[(Point2D<f32>, Segment)],
+        end: Point2D<f32>,
+        close: bool,
+    ) {
+        if subpath.is_empty() {
+            return;
+        }
+        self.segments.push(Segment::MoveTo(end));
+        for &(from, segment) in subpath.iter().rev() {
+            self.segments.push(match segment {
+                Segment::QuadTo(c, _) => Segment::QuadTo(c, from),
+                Segment::CubicTo(c1, c2, _) => Segment::CubicTo(c2, c1, from),
+                _ => Segment::LineTo(from),
+            });
+        }
+        if close {
+            self.close();
+        }
+    }
+
+    /// Creates an ellipse centered at `(x, y)` with the width `w` and height `h`.
+    pub(crate) fn ellipse(x: f32, y: f32, w: f32, h: f32) -> Path {
+        let arc = lyon_geom::Arc {
+            center: point2(x, y),
+            radii: vec2(w / 2., h / 2.),
+            start_angle: Angle::zero(),
+            sweep_angle: Angle::two_pi(),
+            x_rotation: Angle::zero(),
+        };
+
+        let mut path = Path::new();
+        path.arc_to(&arc);
+        // Closing the outline joins its ends, instead of leaving a seam where they're capped.
+        path.close();
+        path
+    }
+
+    /// Creates a cubic Bézier curve from `(x1, y1)` to `(x4, y4)`, using `(x2, y2)` and `(x3, y3)`
+    /// as the control points.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn bezier(
+        x1: f32,
+        y1: f32,
+        x2: f32,
+        y2: f32,
+        x3: f32,
+        y3: f32,
+        x4: f32,
+        y4: f32,
+    ) -> Path {
+        let mut path = Path::new();
+        path.move_to(x1, y1);
+        path.cubic_to(x2, y2, x3, y3, x4, y4);
+        path
+    }
+
+    /// Creates a Catmull-Rom spline segment between the middle two `points`, using the first and
+    /// last points as control points. See [`curve_segment`] for the meaning of `tightness`.
+    pub(crate) fn curve(points: [(f32, f32); 4], tightness: f32) -> Path {
+        let segment = curve_segment(points, tightness);
+        let mut path = Path::new();
+        path.move_to(segment.from.x, segment.from.y);
+        path.cubic(&segment);
+        path
+    }
+
+    /// Creates an arc of the ellipse centered at `(x, y)` with the width `w` and height `h`, from
+    /// the angle `start` to the angle `stop` in radians, closed as specified by `mode`. Angles are
+    /// measured clockwise from the positive x-axis, and the arc is always drawn clockwise from
+    /// `start` to `stop`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn arc(
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        start: f32,
+        stop: f32,
+        mode: ArcMode,
+    ) -> Path {
+        use std::f32::consts::TAU;
+
+        // If the arc goes all the way around, it's just an ellipse.
+        if stop - start >= TAU {
+            return Path::ellipse(x, y, w, h);
+        }
+
+        // Like p5.js, the angles are the actual angles of the end points from the center, rather
+        // than the parametric angles along the ellipse, so they have to be adjusted when the
+        // ellipse isn't a circle.
+        let radii = vec2(w / 2., h / 2.);
+        let parametric = |angle: f32| (radii.x * angle.sin()).atan2(radii.y * angle.cos());
+        let start_angle = parametric(start);
+        let mut sweep = (parametric(stop) - start_angle).rem_euclid(TAU);
+        if sweep == 0. && stop > start {
+            sweep = TAU;
+        }
+
+        let mut path = Path::new();
+        if mode == ArcMode::Pie {
+            path.move_to(x, y);
+        }
+        path.arc_to(&lyon_geom::Arc {
+            center: point2(x, y),
+            radii,
+            start_angle: Angle::radians(start_angle),
+            sweep_angle: Angle::radians(sweep),
+            x_rotation: Angle::zero(),
+        });
+        if mode != ArcMode::Open {
+            path.close();
+        }
+        path
+    }
+
+    /// Creates a line segment between `(x1, y1)` and `(x2, y2)`.
+    pub(crate) fn line(x1: f32, y1: f32, x2: f32, y2: f32) -> Path {
+        let mut path = Path::new();
+        path.move_to(x1, y1);
+        path.line_to(x2, y2);
+        path
+    }
+
+    /// Creates a closed polygon through each of the `points`, in order.
+    pub(crate) fn polygon(points: &[(f32, f32)]) -> Path {
+        let mut path = Path::new();
+        for &(x, y) in points {
+            path.line_to(x, y);
+        }
+        path.close();
+        path
+    }
+
+    /// Creates a rectangle, interpreting `x`, `y`, `w` and `h` according to the `mode`, and
+    /// rounding each corner as specified by `rounding`.
+    pub(crate) fn rect(
+        mode: RectMode,
+        x: f32,
+        y: f32,
+        w: f32,
+        h: f32,
+        rounding: Option<&RectRounding>,
+    ) -> Path {
+        let corners = match mode {
+            RectMode::Corner => [(x, y), (x + w, y), (x + w, y + h), (x, y + h)],
+            RectMode::Corners => [(x, y), (w, y), (w, h), (x, h)],
+            RectMode::Center => [
+                (x - w / 2., y - h / 2.),
+                (x + w / 2., y - h / 2.),
+                (x + w / 2., y + h / 2.),
+                (x - w / 2., y + h / 2.),
+            ],
+            RectMode::Radius => [
+                (x - w, y - h),
+                (x + w, y - h),
+                (x + w, y + h),
+                (x - w, y + h),
+            ],
+        };
+
+        let mut path = Path::new();
+
+        for (i, corner) in corners.iter().enumerate() {
+            let rad = match rounding {
+                Some(rounding) => match i {
+                    0 => rounding.tl,
+                    1 => rounding.tr,
+                    2 => rounding.br,
+                    3 => rounding.bl,
+                    _ => unreachable!("There are only 4 corners, but is `i` more than 3"),
+                },
+                None => 0.,
+            };
+
+            // if there's no rounding, just move along the corners
+            if rad == 0. {
+                path.line_to(corner.0, corner.1);
+                continue;
+            }
+
+            let center = match i {
+                0 => (corner.0 + rad, corner.1 + rad),
+                1 => (corner.0 - rad, corner.1 + rad),
+                2 => (corner.0 - rad, corner.1 - rad),
+                3 => (corner.0 + rad, corner.1 - rad),
+                _ => unreachable!("There are only 4 corners, but is `i` more than 3"),
+            };
+
+            path.arc_to(&lyon_geom::Arc {
+                center: point2(center.0, center.1),
+                radii: vec2(rad, rad),
+                start_angle: Angle::pi() + Angle::frac_pi_2() * (i as f32),
+                sweep_angle: Angle::frac_pi_2(),
+                x_rotation: Angle::zero(),
+            });
+        }
+
+        path.close();
+        path
+    }
+
+    /// Creates the outlines of the glyphs of `s` in the given `font`, with the baseline of the
+    /// text starting at `(x, y)`. `size` is the size of the font in pixels per em.
+    pub(crate) fn text(font: &Font, size: f32, s: &str, x: f32, y: f32) -> Path {
+        let mut sink = GlyphSink {
+            path: Path::new(),
+            origin: (x, y),
+            scale: size / font.metrics().units_per_em as f32,
+        };
+
+        for c in s.chars() {
+            // TODO: Fall back to other fonts for characters that this font doesn't have.
+            let glyph = match font.glyph_for_char(c) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            // Glyphs that fail to load are skipped, much like missing glyphs.
+            let _ = font.outline(glyph, HintingOptions::None, &mut sink);
+            if let Ok(advance) = font.advance(glyph) {
+                sink.origin.0 += advance.x() * sink.scale;
+            }
+        }
+
+        sink.path
+    }
+
+    /// Adds a cubic Bézier `segment` to the path, starting from the current point.
+    pub(crate) fn cubic(&mut self, segment: &CubicBezierSegment<f32>) {
+        let (c1, c2, to) = (segment.ctrl1, segment.ctrl2, segment.to);
+        self.cubic_to(c1.x, c1.y, c2.x, c2.y, to.x, to.y);
+    }
+
+    /// Adds `arc` to the path, with a line from the current point to the start of the arc.
+    fn arc_to(&mut self, arc: &lyon_geom::Arc<f32>) {
+        let start = arc.from();
+        self.line_to(start.x, start.y);
+
+        arc.for_each_quadratic_bezier(&mut |q| {
+            self.quad_to(q.ctrl.x, q.ctrl.y, q.to.x, q.to.y);
+        });
+    }
+}
+
+/// Interprets the parameters of an ellipse according to the `mode`, returning its center, width
+/// and height, in the form expected by [`Path::ellipse`] and [`Path::arc`].
+pub(crate) fn ellipse_bounds(
+    mode: EllipseMode,
+    x: f32,
+    y: f32,
+    w: f32,
+    h: f32,
+) -> (f32, f32, f32, f32) {
+    match mode {
+        EllipseMode::Center => (x, y, w, h),
+        EllipseMode::Radius => (x, y, w * 2., h * 2.),
+        EllipseMode::Corner => (x + w / 2., y + h / 2., w, h),
+        EllipseMode::Corners => ((x + w) / 2., (y + h) / 2., (w - x).abs(), (h - y).abs()),
+    }
+}
+
+/// Converts the Catmull-Rom spline segment between `points[1]` and `points[2]` into a cubic
+/// Bézier curve, where `points[0]` and `points[3]` determine the direction of the curve at each
+/// end.
+///
+/// `tightness` works like in p5.js: 0 gives a Catmull-Rom spline, 1 connects the points with
+/// straight lines, and values outside of that range make the curve loop around the points.
+pub(crate) fn curve_segment(points: [(f32, f32); 4], tightness: f32) -> CubicBezierSegment<f32> {
+    let [p0, p1, p2, p3] = points;
+    let s = (1. - tightness) / 6.;
+    CubicBezierSegment {
+        from: point2(p1.0, p1.1),
+        ctrl1: point2(p1.0 + s * (p2.0 - p0.0), p1.1 + s * (p2.1 - p0.1)),
+        ctrl2: point2(p2.0 - s * (p3.0 - p1.0), p2.1 - s * (p3.1 - p1.1)),
+        to: point2(p2.0, p2.1),
+    }
+}
+
+/// Collects glyph outlines from font-kit into a `Path`. Outlines are given in font units, with the
+/// y-axis pointing up, so they're scaled and flipped to match the canvas.
+struct GlyphSink {
+    path: Path,
+    origin: (f32, f32),
+    scale: f32,
+}
+
+impl GlyphSink {
+    fn point(&self, v: Vector2F) -> (f32, f32) {
+        (
+            self.origin.0 + v.x() * self.scale,
+            self.origin.1 - v.y() * self.scale,
+        )
+    }
+}
+
+impl OutlineSink for GlyphSink {
+    fn move_to(&mut self, to: Vector2F) {
+        let (x, y) = self.point(to);
+        self.path.move_to(x, y);
+    }
+
+    fn line_to(&mut self, to: Vector2F) {
+        let (x, y) = self.point(to);
+        self.path.line_to(x, y);
+    }
+
+    fn quadratic_curve_to(&mut self, ctrl: Vector2F, to: Vector2F) {
+        let ((cx, cy), (x, y)) = (self.point(ctrl), self.point(to));
+        self.path.quad_to(cx, cy, x, y);
+    }
+
+    fn cubic_curve_to(&mut self, ctrl: LineSegment2F, to: Vector2F) {
+        let (c1, c2, to) = (
+            self.point(ctrl.from()),
+            self.point(ctrl.to()),
+            self.point(to),
+        );
+        self.path.cubic_to(c1.0, c1.1, c2.0, c2.1, to.0, to.1);
+    }
+
+    fn close(&mut self) {
+        self.path.close();
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{Color, P5Image};
+
+/// The pixels copied from the canvas by [`load_pixels`](crate::P5Trait::load_pixels), as straight
+/// RGBA components, row by row. Sketches change them with `pixels_mut` and `set`, and then copy
+/// them back onto the canvas with `update_pixels`.
+#[derive(Default)]
+pub(crate) struct Pixels {
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+}
+
+impl Pixels {
+    /// Copies the pixels out of `canvas`, replacing any that were loaded before.
+    pub(crate) fn load(&mut self, canvas: image::RgbaImage) {
+        self.width = canvas.width() as usize;
+        self.height = canvas.height() as usize;
+        self.data = canvas.into_raw();
+    }
+
+    /// Whether the pixels have been loaded.
+    pub(crate) fn is_loaded(&self) -> bool {
+        !self.data.is_empty()
+    }
+
+    /// Returns the pixels, which are empty until they're loaded.
+    pub(crate) fn data_mut(&mut self) -> &mut [u8] {
+        &mut self.data
+    }
+
+    /// Returns the pixels to copy onto the canvas in `update_pixels`. If they haven't been loaded,
+    /// a warning is printed instead, and there's nothing to copy.
+    pub(crate) fn to_update(&self) -> Option<&[u8]> {
+        if !self.is_loaded() {
+            eprintln!("Warning -- `P5::update_pixels` -- `load_pixels` hasn't been called, so there are no pixels to update the canvas with.");
+            return None;
+        }
+        Some(&self.data)
+    }
+
+    /// Sets the pixel at `(x, y)` to `color`. Like with [`pixel_index`], the coordinates are rounded
+    /// down, and pixels outside of the canvas are ignored.
+    pub(crate) fn set(&mut self, x: f32, y: f32, color: Color) {
+        if let Some(i) = pixel_index(x, y, self.width, self.height) {
+            self.data[i * 4..i * 4 + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+}
+
+/// Returns the index of the pixel at `(x, y)` on a canvas of the given size, counting row by row
+/// from the top left, or `None` if it's outside of the canvas. Like in p5.js, the coordinates are
+/// rounded down.
+pub(crate) fn pixel_index(x: f32, y: f32, width: usize, height: usize) -> Option<usize> {
+    let (x, y) = (x.floor(), y.floor());
+    if x >= 0. && y >= 0. && x < width as f32 && y < height as f32 {
+        Some(y as usize * width + x as usize)
+    } else {
+        None
+    }
+}
+
+/// Copies the `w` by `h` rectangle with its top left corner at `(x, y)` out of `canvas`. Like
+/// with [`pixel_index`], the coordinates and size are rounded down, and the parts of the rectangle
+/// outside of the canvas are transparent.
+pub(crate) fn region(canvas: &image::RgbaImage, x: f32, y: f32, w: f32, h: f32) -> P5Image {
+    let (x, y) = (x.floor(), y.floor());
+    let (w, h) = (w.floor().max(0.) as u32, h.floor().max(0.) as u32);
+    let mut image = image::RgbaImage::new(w, h);
+    let size = (canvas.width() as usize, canvas.height() as usize);
+    for (i, j, pixel) in image.enumerate_pixels_mut() {
+        if pixel_index(x + i as f32, y + j as f32, size.0, size.1).is_some() {
+            *pixel = *canvas.get_pixel((x + i as f32) as u32, (y + j as f32) as u32);
+        }
+    }
+    P5Image::from(image)
+}
+
+/// Premultiplies the straight RGBA components of `pixel` by its alpha.
+pub(crate) fn premultiply(pixel: &[u8]) -> [u8; 4] {
+    let a = u16::from(pixel[3]);
+    let c = |c: u8| ((u16::from(c) * a + 127) / 255) as u8;
+    [c(pixel[0]), c(pixel[1]), c(pixel[2]), pixel[3]]
+}
@@ -0,0 +1,149 @@
+use euclid::default::Transform2D;
+use euclid::point2;
+
+/// How much of each pixel in a rectangle of the canvas is covered by the dots drawn by `point` and
+/// `points`. Drawing lots of points as paths is slow, so instead, the backends work out the
+/// coverage of each dot directly, and then draw the whole rectangle at once, like an image. Each
+/// backend keeps one of these, so its buffer is reused rather than allocated every time.
+#[derive(Default)]
+pub(crate) struct PointCoverage {
+    /// The canvas coordinates of the top left corner of the rectangle.
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    /// The coverage of each pixel in the rectangle, row by row, from 0 to 1.
+    pub(crate) coverage: Vec<f32>,
+}
+
+impl PointCoverage {
+    /// Works out the coverage of a dot at each of the `points`, after transforming them by
+    /// `transform`, on a canvas of the given `size`. If the `weight` is 1 or less, each dot is a
+    /// square covering one pixel, with its top left corner at the point. Otherwise, it's a circle
+    /// centered at the point, with a diameter of `weight`. Like the stroke of other shapes, the
+    /// size of the dots isn't affected by `transform`.
+    ///
+    /// Points that aren't finite, or whose dots are completely outside of the canvas, are skipped.
+    /// Returns `false` if that leaves nothing to draw, or if the `weight` is negative or isn't
+    /// finite.
+    pub(crate) fn update(
+        &mut self,
+        points: &[(f32, f32)],
+        transform: &Transform2D<f32>,
+        weight: f32,
+        size: (usize, usize),
+    ) -> bool {
+        if !weight.is_finite() || weight < 0. {
+            return false;
+        }
+
+        // The distance from a point to the edges of its dot.
+        let (before, after) = if weight <= 1. {
+            (0., 1.)
+        } else {
+            (weight / 2., weight / 2.)
+        };
+        let (width, height) = (size.0 as f32, size.1 as f32);
+
+        // Comparisons with NaN are always false, so this also skips points that aren't finite.
+        // The points are transformed again for each pass, rather than being collected.
+        let points = || {
+            points
+                .iter()
+                .map(move |&(x, y)| transform.transform_point(point2(x, y)))
+                .filter(move |p| p.x + after > 0. && p.x - before < width)
+                .filter(move |p| p.y + after > 0. && p.y - before < height)
+        };
+
+        let (mut min_x, mut min_y) = (width, height);
+        let (mut max_x, mut max_y) = (0f32, 0f32);
+        let mut any = false;
+        for p in points() {
+            min_x = min_x.min(p.x - before);
+            min_y = min_y.min(p.y - before);
+            max_x = max_x.max(p.x + after);
+            max_y = max_y.max(p.y + after);
+            any = true;
+        }
+        if !any {
+            return false;
+        }
+        let (x0, y0) = (min_x.floor().max(0.) as i32, min_y.floor().max(0.) as i32);
+        let (x1, y1) = (
+            max_x.ceil().min(width) as i32,
+            max_y.ceil().min(height) as i32,
+        );
+
+        self.x = x0;
+        self.y = y0;
+        self.width = (x1 - x0) as usize;
+        self.height = (y1 - y0) as usize;
+        self.coverage.clear();
+        self.coverage.resize(self.width * self.height, 0.);
+        for p in points() {
+            if weight <= 1. {
+                self.add_square(p.x, p.y);
+            } else {
+                self.add_circle(p.x, p.y, weight / 2.);
+            }
+        }
+        true
+    }
+
+    /// Covers the pixel at `(x, y)` on the canvas by `amount`. Where dots overlap, each pixel is
+    /// only covered by the dot that covers the most of it, so they're drawn as a single shape.
+    fn cover(&mut self, x: i32, y: i32, amount: f32) {
+        let (x, y) = (x - self.x, y - self.y);
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            let pixel = &mut self.coverage[y as usize * self.width + x as usize];
+            *pixel = pixel.max(amount);
+        }
+    }
+
+    /// Adds a square one pixel wide whose top left corner is at `(x, y)`. If it isn't aligned to
+    /// the pixel grid, it covers part of each of the four pixels around it.
+    fn add_square(&mut self, x: f32, y: f32) {
+        let (px, py) = (x.floor(), y.floor());
+        let (fx, fy) = (x - px, y - py);
+        let (px, py) = (px as i32, py as i32);
+
+        self.cover(px, py, (1. - fx) * (1. - fy));
+        self.cover(px + 1, py, fx * (1. - fy));
+        self.cover(px, py + 1, (1. - fx) * fy);
+        self.cover(px + 1, py + 1, fx * fy);
+    }
+
+    /// Adds a circle with the radius `r` centered at `(x, y)`. Each pixel is covered by how far
+    /// its center is inside of the circle, which anti-aliases the edge over one pixel. Only the
+    /// part of the circle inside of the rectangle is visited.
+    fn add_circle(&mut self, x: f32, y: f32, r: f32) {
+        let (x0, y0) = (
+            ((x - r).floor() as i32).max(self.x),
+            ((y - r).floor() as i32).max(self.y),
+        );
+        let (x1, y1) = (
+            ((x + r).ceil() as i32).min(self.x + self.width as i32),
+            ((y + r).ceil() as i32).min(self.y + self.height as i32),
+        );
+        for py in y0..y1 {
+            for px in x0..x1 {
+                let (dx, dy) = (px as f32 + 0.5 - x, py as f32 + 0.5 - y);
+                let amount = (r + 0.5 - (dx * dx + dy * dy).sqrt()).min(1.);
+                if amount > 0. {
+                    self.cover(px, py, amount);
+                }
+            }
+        }
+    }
+
+    /// Returns the premultiplied RGBA components of `color` drawn over each pixel of the
+    /// rectangle, row by row.
+    pub(crate) fn colors(&self, color: crate::Color) -> impl Iterator<Item = [u8; 4]> + '_ {
+        let alpha = f32::from(color.a) / 255.;
+        self.coverage.iter().map(move |&coverage| {
+            let a = alpha * coverage;
+            let c = |c: u8| (f32::from(c) * a).round() as u8;
+            [c(color.r), c(color.g), c(color.b), (255. * a).round() as u8]
+        })
+    }
+}
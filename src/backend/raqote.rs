@@ -1,27 +1,45 @@
-use crate::p5::{RectMode, P5};
-use crate::Sketch;
-use crate::{ColorMode, IntoColor};
-use euclid::{point2, vec2, Angle, Transform2D, UnknownUnit};
-use font_kit::font::Font;
+use crate::backend::clip::Clip;
+use crate::backend::path::{curve_segment, ellipse_bounds, Path, Segment};
+use crate::backend::pixels::{pixel_index, premultiply, region, Pixels};
+use crate::backend::points::PointCoverage;
+use crate::backend::shape::{add_vertex, finish_shape, Shape, Vertex};
+use crate::backend::style::{Style, StyleStack};
+use crate::backend::{image_from_pixels, save_image, Backend};
+use crate::gradient::GradientKind;
+use crate::p5::{
+    AngleMode, ArcMode, BlendMode, EllipseMode, EndMode, ImageMode, RectMode, ShapeKind, StrokeCap,
+    StrokeJoin, P5,
+};
+use crate::p5_image::{image_bounds, source_bounds, to_argb};
+use crate::{ColorMode, Gradient, IntoColor, P5Image, Sketch};
+use euclid::default::Box2D;
+use euclid::Transform2D;
 use raqote::{DrawOptions, DrawTarget, PathBuilder, Source};
 
 /// A structure that contains all the internal state necessary for drawing with the raqote backend.
 pub struct RaqoteP5 {
     /// The raqote [`DrawTarget`](raqote::DrawTarget).
     dt: DrawTarget,
-    /// The fill color used to fill in shapes. If [`None`](std::option::Option), the shape is
-    /// transparent.
-    fill_color: Option<raqote::Color>,
-    /// The color used to draw lines and borders around shapes.
-    stroke_color: raqote::Color,
-    /// The width of the stroke used for lines, points and the border around shapes.
-    stroke_weight: f32,
-    /// The current [`RectMode`](crate::p5::RectMode). The default is RectMode::Corner.
-    rect_mode: RectMode,
-    /// The current transformation that should be applied to shapes.
-    transform: Transform2D<f32, UnknownUnit, UnknownUnit>,
-    /// The current color mode
-    color_mode: ColorMode,
+    /// The current drawing style, which is saved and restored by `push` and `pop`.
+    style: Style,
+    /// The styles saved by `push`.
+    styles: StyleStack,
+    /// The shape whose vertices are being recorded, if `begin_shape` has been called.
+    shape: Option<Shape>,
+    /// The clip whose shapes are being recorded, if `begin_clip` has been called.
+    clip: Option<Clip>,
+    /// The unit that angles are measured in. Like in p5.js, this isn't saved by `push`.
+    angle_mode: AngleMode,
+    /// Whether images are drawn smoothly when they're scaled. Like in p5.js, this isn't saved by
+    /// `push`.
+    smooth: bool,
+    /// The pixels copied from the canvas by `load_pixels`.
+    pixels: Pixels,
+    /// The coverage of the dots drawn by `point` and `points`.
+    point_coverage: PointCoverage,
+    /// The pixels of the dots drawn by `point` and `points`, which are kept so that drawing them
+    /// doesn't allocate a new image each time.
+    dots: Vec<u32>,
     /// The variable frame_count contains the number of frames that have been displayed since the program started. Inside setup() the value is 0, after the first iteration of draw it is 1, etc.
     pub frame_count: usize,
     pub frame_rate: f32,
@@ -30,11 +48,6 @@ pub struct RaqoteP5 {
     pub key: Option<char>,
     /// If `Some`, contains the most recent key pressed on the keyboard as a [`Key`](crate::Key). Instead of a separate `keyIsPressed` variable, this uses an `Option`.
     pub key_code: Option<crate::Key>,
-
-    /// Sets/gets the current font size. This size will be used in all subsequent calls to the text() function. Font size is measured in _points_.
-    text_size: f32,
-    /// The current font
-    font: Font,
 }
 
 impl From<crate::Color> for raqote::Color {
@@ -44,327 +57,860 @@ impl From<crate::Color> for raqote::Color {
 }
 
 impl RaqoteP5 {
-    pub fn new<S: Sketch>(sketch: &S) -> RaqoteP5 {
-        RaqoteP5 {
-            dt: DrawTarget::new(sketch.width() as i32, sketch.height() as i32),
-            fill_color: Some(raqote::Color::new(255, 255, 255, 255)),
-            stroke_color: raqote::Color::new(255, 0, 0, 0),
-            stroke_weight: 1.,
-            rect_mode: RectMode::Corner,
-            transform: Transform2D::identity(),
-            color_mode: crate::RGB,
-            frame_count: 0,
-            // TODO: p5js docs say the default framerate is based on the monitor refresh rate, but we hard code it to be 60.
-            frame_rate: 60.,
-            keys: None,
-            key: None,
-            key_code: None,
-            text_size: 32., // this is what the default text size looks like in p5.js
-            font: font_kit::source::SystemSource::new()
-                .select_best_match(
-                    &[font_kit::family_name::FamilyName::SansSerif],
-                    &font_kit::properties::Properties::default(),
-                )
-                .expect("Default sans-serif font not found")
-                .load()
-                .expect("Failed to load default sans-serif font"),
+    /// Creates a backend with a blank canvas the size of `sketch`. This is the same as calling
+    /// [`Backend::new`] with the sketch's width and height, which is what sketches use to create
+    /// their backend.
+    pub fn new<S: Sketch<RaqoteP5>>(sketch: &S) -> RaqoteP5 {
+        <RaqoteP5 as Backend>::new(sketch.width(), sketch.height())
+    }
+
+    /// Transforms `path` using the current transformation, and converts it into a raqote path.
+    /// Also returns the box around the transformed path.
+    fn transform_path(&self, path: &Path) -> (raqote::Path, Option<Box2D<f32>>) {
+        let path = path.transform(&self.style.transform);
+        (to_raqote_path(&path), path.bounds())
+    }
+
+    /// Creates a raqote stroke style from the stroke weight, cap, join and dash pattern.
+    fn stroke_style(&self) -> raqote::StrokeStyle {
+        raqote::StrokeStyle {
+            width: self.style.stroke_weight,
+            cap: match self.style.stroke_cap {
+                StrokeCap::Round => raqote::LineCap::Round,
+                StrokeCap::Square => raqote::LineCap::Butt,
+                StrokeCap::Project => raqote::LineCap::Square,
+            },
+            join: match self.style.stroke_join {
+                StrokeJoin::Miter => raqote::LineJoin::Miter,
+                StrokeJoin::Bevel => raqote::LineJoin::Bevel,
+                StrokeJoin::Round => raqote::LineJoin::Round,
+            },
+            miter_limit: self.style.miter_limit,
+            dash_array: self.style.dash.clone(),
+            dash_offset: self.style.dash_offset,
         }
     }
 
-    fn transform_path(&self, path: raqote::Path) -> raqote::Path {
-        // Hack because raqote uses an old version of euclid, so we copy the data inside the
-        // transform.
-        let transform = raqote::Transform::from_row_major_array(self.transform.to_array());
-        path.transform(&transform)
+    /// Creates the brush that paints with `color`, or with `gradient` instead if there is one. The
+    /// gradient is transformed by the current transformation, along with the shape being painted.
+    /// `bounds` returns the area of the canvas that will be painted, which is only needed for conic
+    /// gradients.
+    fn brush<F>(&self, color: crate::Color, gradient: Option<&Gradient>, bounds: F) -> Brush
+    where
+        F: FnOnce() -> Option<Box2D<f32>>,
+    {
+        let gradient = match gradient {
+            Some(gradient) => gradient,
+            None => return Brush::Source(solid_source(color)),
+        };
+        if let Some(color) = gradient.solid_color() {
+            return Brush::Source(solid_source(color));
+        }
+
+        let t = &self.style.transform;
+        let inverse = match raqote::Transform::row_major(t.m11, t.m12, t.m21, t.m22, t.m31, t.m32)
+            .inverse()
+        {
+            Some(inverse) => inverse,
+            None => return Brush::Source(solid_source(crate::Color::new(0, 0, 0, 0))),
+        };
+        let stops = raqote::Gradient {
+            stops: gradient
+                .stops
+                .iter()
+                .map(|&(position, color)| raqote::GradientStop {
+                    position,
+                    color: color.into(),
+                })
+                .collect(),
+        };
+
+        let source = match gradient.kind {
+            GradientKind::Linear { x1, y1, x2, y2 } => Source::new_linear_gradient(
+                stops,
+                raqote::Point::new(x1, y1),
+                raqote::Point::new(x2, y2),
+                raqote::Spread::Pad,
+            ),
+            GradientKind::Radial { x, y, r } => {
+                Source::new_radial_gradient(stops, raqote::Point::new(x, y), r, raqote::Spread::Pad)
+            }
+            GradientKind::Conic { .. } => {
+                let (width, height) = (self.dt.width(), self.dt.height());
+                let image = match bounds().and_then(|b| gradient.to_image(t, &b, width, height)) {
+                    Some(image) => image,
+                    None => return Brush::Source(solid_source(crate::Color::new(0, 0, 0, 0))),
+                };
+                let data = image
+                    .pixels
+                    .iter()
+                    .map(|&[r, g, b, a]| u32::from_be_bytes([a, r, g, b]))
+                    .collect();
+                return Brush::Image {
+                    data,
+                    x: image.x,
+                    y: image.y,
+                    width: image.width,
+                    height: image.height,
+                };
+            }
+        };
+
+        // The gradient sources map the coordinates of the canvas onto the gradient, so the
+        // current transformation has to be undone first.
+        Brush::Source(match source {
+            Source::LinearGradient(stops, spread, m) => {
+                Source::LinearGradient(stops, spread, inverse.post_transform(&m))
+            }
+            Source::RadialGradient(stops, spread, m) => {
+                Source::RadialGradient(stops, spread, inverse.post_transform(&m))
+            }
+            source => source,
+        })
+    }
+
+    /// Strokes an already transformed `path` with the current stroke color and stroke style.
+    /// `bounds` is the box around the path.
+    fn stroke_path(&mut self, path: &raqote::Path, bounds: Option<Box2D<f32>>) {
+        let (color, gradient) = self.style.current_stroke();
+        let margin = self.style.stroke_margin();
+        let bounds = || bounds.map(|b| b.inflate(margin, margin));
+        let brush = self.brush(color, gradient, bounds);
+        let style = self.stroke_style();
+        let blend_mode = to_raqote_blend_mode(self.style.current_blend_mode());
+        draw_blended(&mut self.dt, blend_mode, |dt, options| {
+            dt.stroke(path, &brush.source(), &style, options)
+        });
+    }
+
+    /// Fills an already transformed `path` with the current fill color, or with the current fill
+    /// gradient instead if there is one. `bounds` is the box around the path. Does nothing if
+    /// shapes aren't filled.
+    fn fill_path(&mut self, path: &raqote::Path, bounds: Option<Box2D<f32>>) {
+        let (color, gradient) = match self.style.current_fill() {
+            Some(fill) => fill,
+            None => return,
+        };
+        let brush = self.brush(color, gradient, || bounds);
+        let blend_mode = to_raqote_blend_mode(self.style.current_blend_mode());
+        draw_blended(&mut self.dt, blend_mode, |dt, options| {
+            dt.fill(path, &brush.source(), options)
+        });
     }
 
     /// Draws a path correctly using the stroke weight, stroke color, fill color, etc.
-    /// attribiutes. Also transforms `path` using `self.transform` before drawing.
-    fn draw_path(&mut self, path: raqote::Path) {
-        let path = self.transform_path(path);
-        if self.stroke_weight != 0.0 {
-            let stroke_style = {
-                let mut s = raqote::StrokeStyle::default();
-                s.width = self.stroke_weight;
-                s
-            };
+    /// attribiutes. Also transforms `path` using the current transformation before drawing.
+    fn draw_path(&mut self, path: &Path) {
+        if let Some(clip) = &mut self.clip {
+            clip.add(path, &self.style.transform);
+            return;
+        }
 
-            self.dt.stroke(
-                &path,
-                &self.stroke_color.into(),
-                &stroke_style,
-                &DrawOptions::default(),
-            );
+        let (path, bounds) = self.transform_path(path);
+        if self.style.stroke_weight != 0.0 {
+            self.stroke_path(&path, bounds);
         }
+        self.fill_path(&path, bounds);
+    }
+
+    /// Draws `color` over the pixels covered by the dots in `point_coverage`, combining it with
+    /// the canvas using `blend_mode`.
+    fn draw_coverage(&mut self, color: crate::Color, blend_mode: raqote::BlendMode) {
+        let coverage = &self.point_coverage;
+        self.dots.clear();
+        self.dots.extend(
+            coverage
+                .colors(color)
+                .map(|[r, g, b, a]| u32::from_be_bytes([a, r, g, b])),
+        );
+        let image = raqote::Image {
+            width: coverage.width as i32,
+            height: coverage.height as i32,
+            data: &self.dots,
+        };
+        let (x, y) = (coverage.x as f32, coverage.y as f32);
+        let source = Source::Image(
+            image,
+            raqote::ExtendMode::Pad,
+            raqote::FilterMode::Nearest,
+            raqote::Transform::create_translation(-x, -y),
+        );
+        let (width, height) = (coverage.width as f32, coverage.height as f32);
+        draw_blended(&mut self.dt, blend_mode, |dt, options| {
+            fill_rect(dt, x, y, width, height, &source, options)
+        });
+    }
+}
+
+/// What shapes are painted with, which is either a raqote source, or an image covering the part
+/// of the canvas that's painted, with its top-left corner at `(x, y)`. raqote can't draw conic
+/// gradients, so they're drawn into an image instead, which has to outlive the source that draws
+/// it.
+enum Brush {
+    Source(Source<'static>),
+    Image {
+        data: Vec<u32>,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+}
 
-        if let Some(fill_color) = self.fill_color {
-            self.dt.fill(
-                &path,
-                &Source::Solid(fill_color.into()),
-                &DrawOptions::default(),
-            );
+impl Brush {
+    fn source(&self) -> Source<'_> {
+        match self {
+            Brush::Source(source) => source.clone(),
+            Brush::Image {
+                data,
+                x,
+                y,
+                width,
+                height,
+            } => Source::Image(
+                raqote::Image {
+                    width: *width,
+                    height: *height,
+                    data,
+                },
+                raqote::ExtendMode::Pad,
+                raqote::FilterMode::Nearest,
+                raqote::Transform::create_translation(-*x as f32, -*y as f32),
+            ),
         }
     }
 }
 
-fn create_ellipse_path(x: f32, y: f32, w: f32, h: f32) -> raqote::Path {
-    let arc = lyon_geom::Arc {
-        center: point2(x, y),
-        radii: vec2(w / 2., h / 2.),
-        start_angle: Angle::zero(),
-        sweep_angle: Angle::two_pi(),
-        x_rotation: Angle::zero(),
-    };
+/// Draws onto `dt` with `draw`, which is given the options that combine what it draws with the
+/// canvas using `blend_mode`.
+fn draw_blended<F>(dt: &mut DrawTarget, blend_mode: raqote::BlendMode, draw: F)
+where
+    F: FnOnce(&mut DrawTarget, &DrawOptions),
+{
+    if blend_mode == raqote::BlendMode::SoftLight {
+        // raqote's soft light blend mode is broken for all but the darkest colors, so instead,
+        // the shape is drawn onto a layer of its own, which is then blended onto the canvas here.
+        let (width, height) = (dt.width(), dt.height());
+        let mut layer = DrawTarget::new(width, height);
+        draw(&mut layer, &DrawOptions::default());
+        let blended: Vec<u32> = dt
+            .get_data()
+            .iter()
+            .zip(layer.get_data())
+            .map(|(&dst, &src)| match src >> 24 {
+                0 => dst,
+                _ => soft_light(src, dst),
+            })
+            .collect();
+
+        // The result replaces the canvas by drawing it, so that it's still clipped.
+        let image = raqote::Image {
+            width,
+            height,
+            data: &blended,
+        };
+        let source = Source::Image(
+            image,
+            raqote::ExtendMode::Pad,
+            raqote::FilterMode::Nearest,
+            raqote::Transform::identity(),
+        );
+        let options = DrawOptions {
+            blend_mode: raqote::BlendMode::Src,
+            ..DrawOptions::default()
+        };
+        dt.fill_rect(0., 0., width as f32, height as f32, &source, &options);
+    } else {
+        let options = DrawOptions {
+            blend_mode,
+            ..DrawOptions::default()
+        };
+        draw(dt, &options);
+    }
+}
 
+/// Fills the rectangle with `source`, like `DrawTarget::fill_rect`. When the rectangle covers whole
+/// pixels, `fill_rect` blends partly transparent sources with the canvas incorrectly, so the
+/// rectangle is filled as a path instead.
+fn fill_rect(
+    dt: &mut DrawTarget,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    source: &Source,
+    options: &DrawOptions,
+) {
     let mut pb = PathBuilder::new();
+    pb.rect(x, y, width, height);
+    dt.fill(&pb.finish(), source, options);
+}
+
+/// Blends the premultiplied ARGB pixel `src` onto `dst` using the soft light blend mode, as it's
+/// defined in the W3C compositing specification.
+fn soft_light(src: u32, dst: u32) -> u32 {
+    let channel = |pixel: u32, shift: u32| ((pixel >> shift) & 0xff) as f32 / 255.;
+    let (sa, da) = (channel(src, 24), channel(dst, 24));
+    let a = sa + da - sa * da;
+    let blend = |shift: u32| {
+        let (s, d) = (channel(src, shift), channel(dst, shift));
+        let (cs, cb) = (s / sa, if da > 0. { d / da } else { 0. });
+        let b = if cs <= 0.5 {
+            cb - (1. - 2. * cs) * cb * (1. - cb)
+        } else {
+            let d = if cb <= 0.25 {
+                ((16. * cb - 12.) * cb + 4.) * cb
+            } else {
+                cb.sqrt()
+            };
+            cb + (2. * cs - 1.) * (d - cb)
+        };
+        (s * (1. - da) + d * (1. - sa) + sa * da * b).min(a)
+    };
+    let byte = |c: f32| (c.clamp(0., 1.) * 255.).round() as u32;
+    byte(a) << 24 | byte(blend(16)) << 16 | byte(blend(8)) << 8 | byte(blend(0))
+}
 
-    let start = arc.from();
-    pb.line_to(start.x, start.y);
+fn solid_source<'a>(color: crate::Color) -> Source<'a> {
+    Source::Solid(raqote::Color::from(color).into())
+}
 
-    arc.for_each_quadratic_bezier(&mut |q| {
-        pb.quad_to(q.ctrl.x, q.ctrl.y, q.to.x, q.to.y);
-    });
+/// Converts a premultiplied ARGB pixel, as stored in a raqote `DrawTarget`, into straight RGBA
+/// components.
+fn unpremultiply(pixel: u32) -> [u8; 4] {
+    let a = pixel >> 24;
+    let unmultiply = |c: u32| {
+        match a {
+            0 => 0,
+            a => ((c & 0xff) * 255 + a / 2) / a,
+        }
+        .min(255) as u8
+    };
 
+    [
+        unmultiply(pixel >> 16),
+        unmultiply(pixel >> 8),
+        unmultiply(pixel),
+        a as u8,
+    ]
+}
+
+fn to_raqote_blend_mode(mode: BlendMode) -> raqote::BlendMode {
+    match mode {
+        BlendMode::Blend => raqote::BlendMode::SrcOver,
+        BlendMode::Add => raqote::BlendMode::Add,
+        BlendMode::Darkest => raqote::BlendMode::Darken,
+        BlendMode::Lightest => raqote::BlendMode::Lighten,
+        BlendMode::Difference => raqote::BlendMode::Difference,
+        BlendMode::Exclusion => raqote::BlendMode::Exclusion,
+        BlendMode::Multiply => raqote::BlendMode::Multiply,
+        BlendMode::Screen => raqote::BlendMode::Screen,
+        BlendMode::Replace => raqote::BlendMode::Src,
+        BlendMode::Remove => raqote::BlendMode::DstOut,
+        BlendMode::Overlay => raqote::BlendMode::Overlay,
+        BlendMode::HardLight => raqote::BlendMode::HardLight,
+        BlendMode::SoftLight => raqote::BlendMode::SoftLight,
+        BlendMode::Dodge => raqote::BlendMode::ColorDodge,
+        BlendMode::Burn => raqote::BlendMode::ColorBurn,
+    }
+}
+
+fn to_raqote_path(path: &Path) -> raqote::Path {
+    let mut pb = PathBuilder::new();
+    for segment in &path.segments {
+        match *segment {
+            Segment::MoveTo(p) => pb.move_to(p.x, p.y),
+            Segment::LineTo(p) => pb.line_to(p.x, p.y),
+            Segment::QuadTo(c, p) => pb.quad_to(c.x, c.y, p.x, p.y),
+            Segment::CubicTo(c1, c2, p) => pb.cubic_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y),
+            Segment::Close => pb.close(),
+        }
+    }
     pb.finish()
 }
 
+impl Backend for RaqoteP5 {
+    fn new(width: usize, height: usize) -> RaqoteP5 {
+        RaqoteP5 {
+            dt: DrawTarget::new(width as i32, height as i32),
+            style: Style::new(),
+            styles: StyleStack::default(),
+            shape: None,
+            clip: None,
+            angle_mode: AngleMode::Radians,
+            smooth: true,
+            pixels: Pixels::default(),
+            point_coverage: PointCoverage::default(),
+            dots: Vec::new(),
+            frame_count: 0,
+            // TODO: p5js docs say the default framerate is based on the monitor refresh rate, but we hard code it to be 60.
+            frame_rate: 60.,
+            keys: None,
+            key: None,
+            key_code: None,
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.frame_count += 1;
+        self.reset_matrix();
+        for _ in 0..self.style.clip_depth {
+            self.dt.pop_clip();
+        }
+        self.style.clip_depth = 0;
+        self.styles.remove_clips();
+    }
+
+    fn set_keys(&mut self, keys: Option<Vec<crate::Key>>) {
+        if let Some(key) = keys.as_ref().and_then(|keys| keys.last()) {
+            self.key_code = Some(*key);
+        }
+        self.keys = keys;
+    }
+
+    fn target_frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    /// Copies the contents of the draw target into an image, converting raqote's premultiplied
+    /// ARGB pixels into straight RGBA.
+    fn to_image(&self) -> image::RgbaImage {
+        let pixels = self.dt.get_data().iter().map(|&pixel| unpremultiply(pixel));
+        image_from_pixels(self.dt.width() as u32, self.dt.height() as u32, pixels)
+    }
+}
+
 impl P5 for RaqoteP5 {
     fn background<C: IntoColor>(&mut self, c: C) {
-        let c: raqote::Color = c.into_color(self.color_mode).into();
+        let c: raqote::Color = c.into_color(self.style.color_mode).into();
         self.dt.clear(c.into());
     }
 
     fn ellipse(&mut self, x: f32, y: f32, w: f32, h: f32) {
-        self.draw_path(create_ellipse_path(x, y, w, h));
+        let (x, y, w, h) = ellipse_bounds(self.style.ellipse_mode, x, y, w, h);
+        self.draw_path(&Path::ellipse(x, y, w, h));
     }
 
     fn circle(&mut self, x: f32, y: f32, d: f32) {
         self.ellipse(x, y, d, d);
     }
 
+    fn ellipse_mode(&mut self, mode: EllipseMode) {
+        self.style.ellipse_mode = mode;
+    }
+
+    fn arc(&mut self, x: f32, y: f32, w: f32, h: f32, start: f32, stop: f32, mode: ArcMode) {
+        let (x, y, w, h) = ellipse_bounds(self.style.ellipse_mode, x, y, w, h);
+        let (start, stop) = (
+            self.angle_mode.to_radians(start),
+            self.angle_mode.to_radians(stop),
+        );
+        self.draw_path(&Path::arc(x, y, w, h, start, stop, mode));
+    }
+
     fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
-        if self.stroke_weight != 0. {
-            let mut pb = PathBuilder::new();
-            pb.move_to(x1, y1);
-            pb.line_to(x2, y2);
-            let path = pb.finish();
-
-            let mut stroke = raqote::StrokeStyle::default();
-            stroke.width = self.stroke_weight;
-
-            self.dt.stroke(
-                &path,
-                &Source::Solid(self.stroke_color.into()),
-                &stroke,
-                &DrawOptions::default(),
-            );
+        if self.clip.is_some() {
+            return;
+        }
+        if self.style.stroke_weight != 0. {
+            let (path, bounds) = self.transform_path(&Path::line(x1, y1, x2, y2));
+            self.stroke_path(&path, bounds);
         } else {
             eprintln!("Warning -- `P5::line` -- `stroke_weight` is 0., so calling `line`  doesn't do anything. Consider calling `P5::stroke_weight` with a non-zero stroke weight.");
         }
     }
 
     fn point(&mut self, x: f32, y: f32) {
-        if self.stroke_weight == 1. && self.stroke_color.a() == 255 {
-            let point = self.transform.transform_point(point2(x, y));
-            let idx = point.y as i32 * self.dt.width() + point.x as i32;
-            // Safety: A struct with only one field has the same layout as that field. A raqote::Color is just a u32.
-            self.dt.get_data_mut()[idx as usize] =
-                unsafe { std::mem::transmute(self.stroke_color) };
+        self.points(&[(x, y)]);
+    }
+
+    fn points(&mut self, points: &[(f32, f32)]) {
+        if self.style.stroke_weight == 0. || self.clip.is_some() {
+            return;
+        }
+
+        let size = (self.dt.width() as usize, self.dt.height() as usize);
+        let weight = self.style.stroke_weight;
+        if !self
+            .point_coverage
+            .update(points, &self.style.transform, weight, size)
+        {
+            return;
+        }
+
+        let (color, _) = self.style.current_stroke();
+        let blend_mode = self.style.current_blend_mode();
+        if blend_mode == BlendMode::Replace {
+            // The pixels around the dots are transparent, and would replace the canvas too, so the
+            // dots are cut out of the canvas first, and then added into the holes.
+            let opaque = crate::Color::new(0, 0, 0, 255);
+            self.draw_coverage(opaque, raqote::BlendMode::DstOut);
+            self.draw_coverage(color, raqote::BlendMode::Add);
         } else {
-            // TODO: Using an ellipse here is _incredibly_ innefficient for small strokeweights.
-            // Additionally, when alpha != 1, but strokeweight == 1, it may even be incorrect, as
-            // anti-aliasing would cause it to be drawn less brightly than it should be.
-            let path = create_ellipse_path(x, y, self.stroke_weight, self.stroke_weight);
-            self.dt.fill(
-                &self.transform_path(path),
-                &Source::Solid(self.stroke_color.into()),
-                &DrawOptions::default(),
-            );
+            self.draw_coverage(color, to_raqote_blend_mode(blend_mode));
         }
     }
 
     fn stroke_weight(&mut self, weight: f32) {
-        self.stroke_weight = weight;
+        self.style.stroke_weight = weight;
     }
 
     fn no_stroke(&mut self) {
-        self.stroke_weight = 0.0;
+        self.style.stroke_weight = 0.0;
     }
 
     fn stroke<C: IntoColor>(&mut self, color: C) {
-        self.stroke_color = color.into_color(self.color_mode).into();
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_stroke(color);
     }
 
-    fn fill<C: IntoColor>(&mut self, color: C) {
-        self.fill_color = Some(color.into_color(self.color_mode).into());
+    fn stroke_gradient(&mut self, gradient: &Gradient) {
+        self.style.set_stroke_gradient(gradient, self.angle_mode);
     }
 
-    fn quad(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
-        let mut pb = PathBuilder::new();
-        pb.move_to(x1, y1);
-        pb.line_to(x2, y2);
-        pb.line_to(x3, y3);
-        pb.line_to(x4, y4);
-        pb.close();
-        let path = pb.finish();
+    fn stroke_cap(&mut self, cap: StrokeCap) {
+        self.style.stroke_cap = cap;
+    }
 
-        self.draw_path(path);
+    fn stroke_join(&mut self, join: StrokeJoin) {
+        self.style.stroke_join = join;
     }
 
-    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, rounding: Option<crate::p5::RectRounding>) {
-        let mut pb = PathBuilder::new();
-
-        let corners = match self.rect_mode {
-            RectMode::Corner => [(x, y), (x + w, y), (x + w, y + h), (x, y + h)],
-            RectMode::Corners => [(x, y), (w, y), (w, h), (x, h)],
-            RectMode::Center => [
-                (x - w / 2., y - h / 2.),
-                (x + w / 2., y - h / 2.),
-                (x + w / 2., y + h / 2.),
-                (x - w / 2., y + h / 2.),
-            ],
-            RectMode::Radius => [
-                (x - w, y - h),
-                (x + w, y - h),
-                (x + w, y + h),
-                (x - w, y + h),
-            ],
-        };
+    fn miter_limit(&mut self, limit: f32) {
+        self.style.miter_limit = limit;
+    }
 
-        for (i, corner) in corners.iter().enumerate() {
-            if let Some(rounding) = &rounding {
-                let rad = match i {
-                    0 => rounding.tl,
-                    1 => rounding.tr,
-                    2 => rounding.br,
-                    3 => rounding.bl,
-                    _ => unreachable!("There are only 4 corners, but is `i` more than 3"),
-                };
+    fn stroke_dash(&mut self, pattern: &[f32], offset: f32) {
+        self.style.set_stroke_dash(pattern, offset);
+    }
 
-                if rad == 0. {
-                    break; // just do the default thing
-                }
+    fn fill<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_fill(Some(color));
+    }
 
-                let center = match i {
-                    0 => (corner.0 + rad, corner.1 + rad),
-                    1 => (corner.0 - rad, corner.1 + rad),
-                    2 => (corner.0 - rad, corner.1 - rad),
-                    3 => (corner.0 + rad, corner.1 - rad),
-                    _ => unreachable!("There are only 4 corners, but is `i` more than 3"),
-                };
+    fn fill_gradient(&mut self, gradient: &Gradient) {
+        self.style.set_fill_gradient(gradient, self.angle_mode);
+    }
 
-                let start = Angle::pi() + Angle::frac_pi_2() * (i as f32);
+    fn quad(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        self.draw_path(&Path::polygon(&[(x1, y1), (x2, y2), (x3, y3), (x4, y4)]));
+    }
 
-                let tl_arc = lyon_geom::Arc {
-                    center: point2(center.0, center.1),
-                    radii: vec2(rad, rad),
-                    start_angle: start,
-                    sweep_angle: Angle::frac_pi_2(),
-                    x_rotation: Angle::zero(),
-                };
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, rounding: Option<crate::p5::RectRounding>) {
+        let path = Path::rect(self.style.rect_mode, x, y, w, h, rounding.as_ref());
+        self.draw_path(&path);
+    }
+
+    fn rect_mode(&mut self, mode: RectMode) {
+        self.style.rect_mode = mode;
+    }
 
-                let start = tl_arc.from();
-                pb.line_to(start.x, start.y);
+    fn triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
+        self.draw_path(&Path::polygon(&[(x1, y1), (x2, y2), (x3, y3)]));
+    }
 
-                tl_arc.for_each_quadratic_bezier(&mut |q| {
-                    pb.quad_to(q.ctrl.x, q.ctrl.y, q.to.x, q.to.y)
-                });
+    fn begin_shape(&mut self, kind: ShapeKind) {
+        self.shape = Some(Shape::new(kind));
+    }
 
-                continue;
-            }
+    fn vertex(&mut self, x: f32, y: f32) {
+        add_vertex(&mut self.shape, Vertex::Line(x, y), "vertex");
+    }
 
-            // if there's no rounding, just move along the corners
-            pb.line_to(corner.0, corner.1);
+    fn end_shape(&mut self, mode: EndMode) {
+        let (points, paths) = finish_shape(&mut self.shape, mode, self.style.curve_tightness);
+        if !points.is_empty() {
+            self.points(&points);
+        }
+        for path in paths {
+            self.draw_path(&path);
         }
+    }
 
-        pb.close();
+    fn bezier_vertex(&mut self, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        let vertex = Vertex::bezier(x2, y2, x3, y3, x4, y4);
+        add_vertex(&mut self.shape, vertex, "bezier_vertex");
+    }
 
-        let path = pb.finish();
-        self.draw_path(path);
+    fn quadratic_vertex(&mut self, cx: f32, cy: f32, x3: f32, y3: f32) {
+        let vertex = Vertex::quadratic(cx, cy, x3, y3);
+        add_vertex(&mut self.shape, vertex, "quadratic_vertex");
     }
 
-    fn rect_mode(&mut self, mode: RectMode) {
-        self.rect_mode = mode;
+    fn curve_vertex(&mut self, x: f32, y: f32) {
+        add_vertex(&mut self.shape, Vertex::Curve(x, y), "curve_vertex");
     }
 
-    fn triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
-        let mut pb = PathBuilder::new();
-        pb.move_to(x1, y1);
-        pb.line_to(x2, y2);
-        pb.line_to(x3, y3);
-        pb.close();
-        let path = pb.finish();
+    fn bezier(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        self.draw_path(&Path::bezier(x1, y1, x2, y2, x3, y3, x4, y4));
+    }
+
+    fn curve(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        let points = [(x1, y1), (x2, y2), (x3, y3), (x4, y4)];
+        self.draw_path(&Path::curve(points, self.style.curve_tightness));
+    }
 
-        self.draw_path(path);
+    fn curve_tightness(&mut self, amount: f32) {
+        self.style.curve_tightness = amount;
+    }
+
+    fn curve_point(&self, a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+        let points = [(a, 0.), (b, 0.), (c, 0.), (d, 0.)];
+        curve_segment(points, self.style.curve_tightness).x(t)
     }
 
     fn reset_matrix(&mut self) {
-        self.transform = Transform2D::identity();
+        self.style.transform = Transform2D::identity();
     }
 
     fn apply_matrix(&mut self, m11: f32, m12: f32, m21: f32, m22: f32, m31: f32, m32: f32) {
-        self.transform = self
-            .transform
-            .then(&Transform2D::new(m11, m12, m21, m22, m31, m32));
+        let matrix = Transform2D::new(m11, m12, m21, m22, m31, m32);
+        self.style.apply_matrix(&matrix);
+    }
+
+    fn push(&mut self) {
+        self.styles.push(&self.style);
+    }
+
+    fn pop(&mut self) {
+        let clip_depth = self.style.clip_depth;
+        self.styles.pop(&mut self.style);
+        for _ in self.style.clip_depth..clip_depth {
+            self.dt.pop_clip();
+        }
+    }
+
+    fn begin_clip(&mut self, invert: bool) {
+        self.clip = Some(Clip::new(invert));
+    }
+
+    fn end_clip(&mut self) {
+        let clip = match self.clip.take() {
+            Some(clip) => clip,
+            None => {
+                eprintln!("Warning -- `P5::end_clip` -- `end_clip` was called without a matching call to `begin_clip`, so there is no clip to apply.");
+                return;
+            }
+        };
+
+        if clip.invert {
+            // raqote can only clip to the inside of a path, so the outside of the shapes is
+            // clipped to by clipping to the outside of each shape in turn, which is the inside of
+            // a path around the whole canvas with the shape cut out of it.
+            let (w, h) = (self.dt.width() as f32, self.dt.height() as f32);
+            for path in &clip.paths {
+                let mut outside = Path::polygon(&[(0., 0.), (w, 0.), (w, h), (0., h)]);
+                outside.segments.extend_from_slice(&path.segments);
+                let mut outside = to_raqote_path(&outside);
+                outside.winding = raqote::Winding::EvenOdd;
+                self.dt.push_clip(&outside);
+                self.style.clip_depth += 1;
+            }
+        } else {
+            self.dt.push_clip(&to_raqote_path(&clip.union()));
+            self.style.clip_depth += 1;
+        }
     }
 
     fn no_fill(&mut self) {
-        self.fill_color = None;
+        self.style.set_fill(None);
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode) {
+        self.style.blend_mode = mode;
+    }
+
+    fn erase(&mut self, fill_strength: f32, stroke_strength: f32) {
+        self.style.set_erase(Some((fill_strength, stroke_strength)));
+    }
+
+    fn no_erase(&mut self) {
+        self.style.set_erase(None);
     }
 
     fn frame_rate(&mut self, fps: f32) {
         self.frame_rate = fps;
     }
 
+    fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
     fn color_mode(&mut self, mode: ColorMode) {
-        self.color_mode = mode;
+        self.style.color_mode = mode;
+    }
+
+    fn angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    fn get_angle_mode(&self) -> AngleMode {
+        self.angle_mode
     }
 
     fn key_is_down(&self, key: crate::Key) -> bool {
         // TODO: Instead of calling `contains`, directly use the `window.is_key_down`
         // function
-        self.keys.as_ref().map_or(false, |keys| keys.contains(&key))
+        self.keys.as_ref().is_some_and(|keys| keys.contains(&key))
     }
 
     fn text(&mut self, s: &str, x: f32, y: f32) {
-        if let Some(fill_color) = self.fill_color {
-            let mut options = DrawOptions::new();
-            options.antialias = raqote::AntialiasMode::Gray;
-            self.dt.draw_text(
-                &self.font,
-                self.text_size,
-                s,
-                raqote::Point::new(x, y),
-                &Source::Solid(fill_color.into()),
-                &options,
-            );
+        if let Some(clip) = &mut self.clip {
+            let path = Path::text(&self.style.font, self.style.text_size, s, x, y);
+            clip.add(&path, &self.style.transform);
+            return;
+        }
+        // The glyphs are filled as paths, rather than drawn with `DrawTarget::draw_text`, so that
+        // they're transformed by the current transformation, like everything else.
+        if self.style.fill_color.is_some() {
+            let path = Path::text(&self.style.font, self.style.text_size, s, x, y);
+            let (path, bounds) = self.transform_path(&path);
+            self.fill_path(&path, bounds);
         }
     }
 
     fn text_size(&mut self, size: f32) {
-        self.text_size = size;
+        self.style.text_size = size;
     }
 
-    // TODO: Better error handling here
     fn text_font(&mut self, family_name: &str) {
-        use font_kit::{family_name::FamilyName, properties::Properties, source::SystemSource};
-        let family_name = match family_name {
-            "serif" => FamilyName::Serif,
-            "sans-serif" => FamilyName::SansSerif,
-            "monospace" => FamilyName::Monospace,
-            "cursive" => FamilyName::Cursive,
-            "fantasy" => FamilyName::Fantasy,
-            x => FamilyName::Title(x.to_owned()),
+        self.style.font = crate::backend::load_font(family_name);
+    }
+
+    fn image_region(
+        &mut self,
+        img: &P5Image,
+        dx: f32,
+        dy: f32,
+        dw: f32,
+        dh: f32,
+        sx: f32,
+        sy: f32,
+        sw: f32,
+        sh: f32,
+    ) {
+        if self.clip.is_some() {
+            return;
+        }
+        let dest = image_bounds(self.style.image_mode, dx, dy, dw, dh);
+        let (dest, source) = match img.placement(dest, source_bounds(sx, sy, sw, sh)) {
+            Some(placement) => placement,
+            None => return,
+        };
+
+        let tinted;
+        let data = match self.style.tint {
+            Some(_) => {
+                tinted = to_argb(&img.tinted_data(self.style.tint));
+                &tinted
+            }
+            None => img.argb_data(),
+        };
+        let image = raqote::Image {
+            width: img.width() as i32,
+            height: img.height() as i32,
+            data,
         };
+        let filter = if self.smooth {
+            raqote::FilterMode::Bilinear
+        } else {
+            raqote::FilterMode::Nearest
+        };
+        // This is how `draw_image_with_size_at` draws images, except that it can only draw whole
+        // images, and always blends between their pixels.
+        let (x, y) = (dest.origin.x, dest.origin.y);
+        let (width, height) = (dest.size.width, dest.size.height);
+        let source = Source::Image(
+            image,
+            raqote::ExtendMode::Pad,
+            filter,
+            raqote::Transform::create_translation(-x, -y)
+                .post_scale(source.size.width / width, source.size.height / height)
+                .post_transform(&raqote::Transform::create_translation(
+                    source.origin.x,
+                    source.origin.y,
+                )),
+        );
+        let t = &self.style.transform;
+        let transform = raqote::Transform::row_major(t.m11, t.m12, t.m21, t.m22, t.m31, t.m32);
+        let blend_mode = to_raqote_blend_mode(self.style.current_blend_mode());
+        draw_blended(&mut self.dt, blend_mode, |dt, options| {
+            dt.set_transform(&transform);
+            fill_rect(dt, x, y, width, height, &source, options);
+            dt.set_transform(&raqote::Transform::identity());
+        });
+    }
 
-        self.font = SystemSource::new()
-            .select_best_match(&[family_name], &Properties::default())
-            .expect("Invalid font specified")
-            .load()
-            .expect("Failed to load font.");
+    fn image_mode(&mut self, mode: ImageMode) {
+        self.style.image_mode = mode;
+    }
+
+    fn tint<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_tint(Some(color));
+    }
+
+    fn no_tint(&mut self) {
+        self.style.set_tint(None);
+    }
+
+    fn smooth(&mut self) {
+        self.smooth = true;
+    }
+
+    fn no_smooth(&mut self) {
+        self.smooth = false;
     }
 
     fn get_data(&self) -> &[u32] {
         self.dt.get_data()
     }
+
+    fn load_pixels(&mut self) {
+        let canvas = self.to_image();
+        self.pixels.load(canvas);
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        self.pixels.data_mut()
+    }
+
+    fn update_pixels(&mut self) {
+        let pixels = match self.pixels.to_update() {
+            Some(pixels) => pixels.chunks_exact(4),
+            None => return,
+        };
+        for (pixel, rgba) in self.dt.get_data_mut().iter_mut().zip(pixels) {
+            let [r, g, b, a] = premultiply(rgba);
+            *pixel = u32::from_be_bytes([a, r, g, b]);
+        }
+    }
+
+    fn get(&self, x: f32, y: f32) -> crate::Color {
+        let (width, height) = (self.dt.width() as usize, self.dt.height() as usize);
+        match pixel_index(x, y, width, height) {
+            Some(i) => {
+                let [r, g, b, a] = unpremultiply(self.dt.get_data()[i]);
+                crate::Color::new(r, g, b, a)
+            }
+            None => crate::Color::new(0, 0, 0, 0),
+        }
+    }
+
+    fn get_region(&self, x: f32, y: f32, w: f32, h: f32) -> P5Image {
+        region(&self.to_image(), x, y, w, h)
+    }
+
+    fn set<C: IntoColor>(&mut self, x: f32, y: f32, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        if !self.pixels.is_loaded() {
+            self.load_pixels();
+        }
+        self.pixels.set(x, y, color);
+    }
+
+    fn save_canvas<P: AsRef<std::path::Path>>(&self, path: P) -> image::ImageResult<()> {
+        save_image(self.to_image(), path)
+    }
 }
@@ -0,0 +1,183 @@
+use crate::backend::path::{curve_segment, Path};
+use crate::p5::{EndMode, ShapeKind};
+
+/// A single vertex of a [`Shape`](crate::backend::shape::Shape), along with how it's connected to
+/// the vertex before it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum Vertex {
+    /// A vertex added by `vertex`, connected with a straight line.
+    Line(f32, f32),
+    /// A vertex added by `quadratic_vertex`, connected with a quadratic Bézier curve through the
+    /// control point `(cx, cy)`.
+    Quadratic { cx: f32, cy: f32, x: f32, y: f32 },
+    /// A vertex added by `bezier_vertex`, connected with a cubic Bézier curve through the control
+    /// points `(cx1, cy1)` and `(cx2, cy2)`.
+    Bezier {
+        cx1: f32,
+        cy1: f32,
+        cx2: f32,
+        cy2: f32,
+        x: f32,
+        y: f32,
+    },
+    /// A vertex added by `curve_vertex`, which is part of a Catmull-Rom spline with the curve
+    /// vertices around it.
+    Curve(f32, f32),
+}
+
+impl Vertex {
+    /// The vertex added by `quadratic_vertex`.
+    pub(crate) fn quadratic(cx: f32, cy: f32, x: f32, y: f32) -> Vertex {
+        Vertex::Quadratic { cx, cy, x, y }
+    }
+
+    /// The vertex added by `bezier_vertex`.
+    pub(crate) fn bezier(cx1: f32, cy1: f32, cx2: f32, cy2: f32, x: f32, y: f32) -> Vertex {
+        Vertex::Bezier {
+            cx1,
+            cy1,
+            cx2,
+            cy2,
+            x,
+            y,
+        }
+    }
+
+    /// The point that this vertex ends at.
+    fn point(&self) -> (f32, f32) {
+        match *self {
+            Vertex::Line(x, y) | Vertex::Curve(x, y) => (x, y),
+            Vertex::Quadratic { x, y, .. } | Vertex::Bezier { x, y, .. } => (x, y),
+        }
+    }
+}
+
+/// A shape whose vertices are being recorded between `begin_shape` and `end_shape`.
+#[derive(Debug, Clone)]
+pub(crate) struct Shape {
+    pub(crate) kind: ShapeKind,
+    pub(crate) vertices: Vec<Vertex>,
+}
+
+impl Shape {
+    pub(crate) fn new(kind: ShapeKind) -> Shape {
+        Shape {
+            kind,
+            vertices: Vec::new(),
+        }
+    }
+
+    /// Returns the point that each vertex ends at, ignoring any curves between them.
+    pub(crate) fn points(&self) -> Vec<(f32, f32)> {
+        self.vertices.iter().map(Vertex::point).collect()
+    }
+
+    /// Splits the shape into the paths that should be drawn for its `kind`. Any vertices left
+    /// over at the end, which aren't enough to make another line, triangle or quad, are ignored.
+    /// `tightness` is the curve tightness used for curve vertices.
+    ///
+    /// Curves are only supported by shapes of the kind `ShapeKind::Polygon`, since the other
+    /// kinds only connect their vertices with straight lines. Shapes of the kind
+    /// `ShapeKind::Points` don't have any paths, since each vertex is drawn as a point instead.
+    pub(crate) fn paths(&self, mode: EndMode, tightness: f32) -> Vec<Path> {
+        let v = self.points();
+        match self.kind {
+            ShapeKind::Polygon if !v.is_empty() => vec![self.polygon(mode, tightness)],
+            ShapeKind::Polygon | ShapeKind::Points => Vec::new(),
+            ShapeKind::Lines => v
+                .chunks_exact(2)
+                .map(|l| Path::line(l[0].0, l[0].1, l[1].0, l[1].1))
+                .collect(),
+            ShapeKind::Triangles => v.chunks_exact(3).map(Path::polygon).collect(),
+            ShapeKind::TriangleFan => (2..v.len())
+                .map(|i| Path::polygon(&[v[0], v[i - 1], v[i]]))
+                .collect(),
+            ShapeKind::TriangleStrip => v.windows(3).map(Path::polygon).collect(),
+            ShapeKind::Quads => v.chunks_exact(4).map(Path::polygon).collect(),
+            ShapeKind::QuadStrip => (3..v.len())
+                .step_by(2)
+                .map(|i| Path::polygon(&[v[i - 3], v[i - 2], v[i], v[i - 1]]))
+                .collect(),
+        }
+    }
+
+    /// Builds the path through every vertex of a `ShapeKind::Polygon`.
+    fn polygon(&self, mode: EndMode, tightness: f32) -> Path {
+        let mut path = Path::new();
+        let mut i = 0;
+        while i < self.vertices.len() {
+            match self.vertices[i] {
+                Vertex::Line(x, y) => path.line_to(x, y),
+                // Like in p5.js, a curve needs a vertex to start from, so if there isn't one, the
+                // curve just starts the path at its end point instead.
+                Vertex::Quadratic { x, y, .. } | Vertex::Bezier { x, y, .. }
+                    if path.segments.is_empty() =>
+                {
+                    path.move_to(x, y)
+                }
+                Vertex::Quadratic { cx, cy, x, y } => path.quad_to(cx, cy, x, y),
+                Vertex::Bezier {
+                    cx1,
+                    cy1,
+                    cx2,
+                    cy2,
+                    x,
+                    y,
+                } => path.cubic_to(cx1, cy1, cx2, cy2, x, y),
+                Vertex::Curve(..) => {
+                    // A run of curve vertices makes a single spline, which passes through every
+                    // vertex except the first and last, since those only control its direction.
+                    let end = self.vertices[i..]
+                        .iter()
+                        .position(|v| !matches!(v, Vertex::Curve(..)))
+                        .map_or(self.vertices.len(), |len| i + len);
+                    let points: Vec<(f32, f32)> =
+                        self.vertices[i..end].iter().map(Vertex::point).collect();
+
+                    if points.len() >= 4 {
+                        path.line_to(points[1].0, points[1].1);
+                        for p in points.windows(4) {
+                            path.cubic(&curve_segment([p[0], p[1], p[2], p[3]], tightness));
+                        }
+                    }
+                    i = end;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+
+        if mode == EndMode::Close {
+            path.close();
+        }
+        path
+    }
+}
+
+/// Adds `vertex` to the `shape` being recorded. If there isn't one, since `begin_shape` hasn't been
+/// called, a warning is printed for the P5 `function` that added the vertex.
+pub(crate) fn add_vertex(shape: &mut Option<Shape>, vertex: Vertex, function: &str) {
+    match shape {
+        Some(shape) => shape.vertices.push(vertex),
+        None => eprintln!("Warning -- `P5::{0}` -- `{0}` was called outside of `begin_shape` and `end_shape`, so it doesn't do anything. Call `P5::begin_shape` first.", function),
+    }
+}
+
+/// Takes the `shape` being recorded, since `end_shape` has been called, and returns the points and
+/// the paths that should be drawn for it. The points are only used by shapes of the kind
+/// `ShapeKind::Points`. If there isn't a shape, since `begin_shape` hasn't been called, a warning
+/// is printed and there's nothing to draw.
+pub(crate) fn finish_shape(
+    shape: &mut Option<Shape>,
+    mode: EndMode,
+    tightness: f32,
+) -> (Vec<(f32, f32)>, Vec<Path>) {
+    match shape.take() {
+        Some(shape) if shape.kind == ShapeKind::Points => (shape.points(), Vec::new()),
+        Some(shape) => (Vec::new(), shape.paths(mode, tightness)),
+        None => {
+            eprintln!("Warning -- `P5::end_shape` -- `end_shape` was called without a matching call to `begin_shape`, so there is no shape to draw.");
+            (Vec::new(), Vec::new())
+        }
+    }
+}
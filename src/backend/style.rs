@@ -0,0 +1,230 @@
+use crate::p5::{AngleMode, BlendMode, EllipseMode, ImageMode, RectMode, StrokeCap, StrokeJoin};
+use crate::{Color, ColorMode, Gradient};
+use euclid::default::Transform2D;
+use font_kit::font::Font;
+
+/// The drawing style and transformation that shapes are drawn with. This is everything that
+/// [`push`](crate::P5Trait::push) saves and [`pop`](crate::P5Trait::pop) restores.
+#[derive(Clone)]
+pub(crate) struct Style {
+    /// The fill color used to fill in shapes. If [`None`](std::option::Option), the shape is
+    /// transparent.
+    pub(crate) fill_color: Option<Color>,
+    /// The gradient used to fill in shapes instead of `fill_color`, if there is one. In that case,
+    /// `fill_color` is the gradient's fallback color, for anything that can't be drawn with it.
+    pub(crate) fill_gradient: Option<Gradient>,
+    /// The color used to draw lines and borders around shapes.
+    pub(crate) stroke_color: Color,
+    /// The gradient used to draw lines and borders around shapes instead of `stroke_color`, if
+    /// there is one. Like `fill_gradient`, `stroke_color` is then the gradient's fallback color.
+    pub(crate) stroke_gradient: Option<Gradient>,
+    /// The width of the stroke used for lines, points and the border around shapes.
+    pub(crate) stroke_weight: f32,
+    /// How the ends of lines are drawn.
+    pub(crate) stroke_cap: StrokeCap,
+    /// How the segments of lines are joined.
+    pub(crate) stroke_join: StrokeJoin,
+    /// The longest a mitered joint can be, relative to the stroke weight, before it's beveled.
+    pub(crate) miter_limit: f32,
+    /// The lengths of the dashes and gaps in the stroke, which always has an even number of
+    /// lengths. If it's empty, the stroke is solid.
+    pub(crate) dash: Vec<f32>,
+    /// How far into the dash pattern the stroke starts.
+    pub(crate) dash_offset: f32,
+    /// The current [`RectMode`](crate::p5::RectMode). The default is RectMode::Corner.
+    pub(crate) rect_mode: RectMode,
+    /// The current [`EllipseMode`](crate::p5::EllipseMode). The default is EllipseMode::Center.
+    pub(crate) ellipse_mode: EllipseMode,
+    /// The current [`ImageMode`](crate::p5::ImageMode). The default is ImageMode::Corner.
+    pub(crate) image_mode: ImageMode,
+    /// The color that images are multiplied by when they're drawn, if `tint` has been called.
+    pub(crate) tint: Option<Color>,
+    /// The current transformation that should be applied to shapes.
+    pub(crate) transform: Transform2D<f32>,
+    /// The current color mode
+    pub(crate) color_mode: ColorMode,
+    /// How new shapes are combined with what's already on the canvas.
+    pub(crate) blend_mode: BlendMode,
+    /// The strengths that the fill and stroke of shapes erase the canvas with, from 0 to 255, if
+    /// `erase` has been called.
+    pub(crate) erase: Option<(u8, u8)>,
+    /// How many clips the backend has applied. `pop` uses this to remove the ones applied since
+    /// the matching `push`.
+    pub(crate) clip_depth: usize,
+    /// The tightness of curves drawn with `curve` and `curve_vertex`, set by `curve_tightness`.
+    pub(crate) curve_tightness: f32,
+    /// Sets/gets the current font size. This size will be used in all subsequent calls to the text() function. Font size is measured in _points_.
+    pub(crate) text_size: f32,
+    /// The current font
+    pub(crate) font: Font,
+}
+
+impl Style {
+    /// Creates the default style, which draws shapes with a white fill and a black stroke one
+    /// pixel wide.
+    pub(crate) fn new() -> Style {
+        Style {
+            fill_color: Some(Color::new(255, 255, 255, 255)),
+            fill_gradient: None,
+            stroke_color: Color::new(0, 0, 0, 255),
+            stroke_gradient: None,
+            stroke_weight: 1.,
+            stroke_cap: StrokeCap::Square,
+            stroke_join: StrokeJoin::Miter,
+            miter_limit: 10.,
+            dash: Vec::new(),
+            dash_offset: 0.,
+            rect_mode: RectMode::Corner,
+            ellipse_mode: EllipseMode::Center,
+            image_mode: ImageMode::Corner,
+            tint: None,
+            transform: Transform2D::identity(),
+            color_mode: crate::RGB,
+            blend_mode: BlendMode::Blend,
+            erase: None,
+            clip_depth: 0,
+            curve_tightness: 0.,
+            text_size: 32., // this is what the default text size looks like in p5.js
+            font: crate::backend::default_font(),
+        }
+    }
+
+    /// Returns the color that shapes are filled with, and the gradient used instead if there is
+    /// one, or `None` if shapes aren't filled. While erasing, this is white with the fill strength
+    /// as its alpha, since only the alpha matters when erasing.
+    pub(crate) fn current_fill(&self) -> Option<(Color, Option<&Gradient>)> {
+        let color = self.fill_color?;
+        Some(match self.erase {
+            Some((strength, _)) => (Color::new(255, 255, 255, strength), None),
+            None => (color, self.fill_gradient.as_ref()),
+        })
+    }
+
+    /// Returns the color that lines, points and the borders around shapes are drawn with, and the
+    /// gradient used instead if there is one. Like with `current_fill`, while erasing, this is
+    /// white with the stroke strength as its alpha.
+    pub(crate) fn current_stroke(&self) -> (Color, Option<&Gradient>) {
+        match self.erase {
+            Some((_, strength)) => (Color::new(255, 255, 255, strength), None),
+            None => (self.stroke_color, self.stroke_gradient.as_ref()),
+        }
+    }
+
+    /// Returns the blend mode that shapes are drawn with, which is `BlendMode::Remove` while
+    /// erasing.
+    pub(crate) fn current_blend_mode(&self) -> BlendMode {
+        match self.erase {
+            Some(_) => BlendMode::Remove,
+            None => self.blend_mode,
+        }
+    }
+
+    /// Fills shapes with `color`, or doesn't fill them if it's `None`, replacing any gradient that
+    /// they were filled with.
+    pub(crate) fn set_fill(&mut self, color: Option<Color>) {
+        self.fill_color = color;
+        self.fill_gradient = None;
+    }
+
+    /// Strokes shapes with `color`, replacing any gradient that they were stroked with.
+    pub(crate) fn set_stroke(&mut self, color: Color) {
+        self.stroke_color = color;
+        self.stroke_gradient = None;
+    }
+
+    /// Returns how far outside of a path its stroke can reach, which is half the stroke weight, or
+    /// further at square caps and mitered joints.
+    pub(crate) fn stroke_margin(&self) -> f32 {
+        self.stroke_weight / 2. * self.miter_limit.max(std::f32::consts::SQRT_2)
+    }
+
+    /// Multiplies images by the `tint` color when they're drawn, as described in
+    /// [`tint`](crate::P5Trait::tint), or stops tinting them if it's `None`.
+    pub(crate) fn set_tint(&mut self, tint: Option<Color>) {
+        self.tint = tint;
+    }
+
+    /// Applies `matrix` to the current transformation, as described in
+    /// [`apply_matrix`](crate::P5Trait::apply_matrix). Like in p5, `matrix` is applied to shapes
+    /// before the transformations that were already there, so each call transforms the coordinate
+    /// system that the previous ones set up.
+    pub(crate) fn apply_matrix(&mut self, matrix: &Transform2D<f32>) {
+        self.transform = matrix.then(&self.transform);
+    }
+
+    /// Starts erasing with the fill and stroke `strengths`, as described in
+    /// [`erase`](crate::P5Trait::erase), or stops erasing if they're `None`.
+    pub(crate) fn set_erase(&mut self, strengths: Option<(f32, f32)>) {
+        let strength = |s: f32| {
+            if s.is_nan() {
+                0
+            } else {
+                s.clamp(0., 255.).round() as u8
+            }
+        };
+        self.erase = strengths.map(|(fill, stroke)| (strength(fill), strength(stroke)));
+    }
+
+    /// Fills shapes with `gradient`, as described in
+    /// [`fill_gradient`](crate::P5Trait::fill_gradient). The angle of a conic gradient is in the
+    /// `angle_mode`.
+    pub(crate) fn set_fill_gradient(&mut self, gradient: &Gradient, angle_mode: AngleMode) {
+        self.fill_color = Some(gradient.fallback_color());
+        self.fill_gradient = Some(gradient.to_radians(angle_mode));
+    }
+
+    /// Strokes shapes with `gradient`, as described in
+    /// [`stroke_gradient`](crate::P5Trait::stroke_gradient). The angle of a conic gradient is in the
+    /// `angle_mode`.
+    pub(crate) fn set_stroke_gradient(&mut self, gradient: &Gradient, angle_mode: AngleMode) {
+        self.stroke_color = gradient.fallback_color();
+        self.stroke_gradient = Some(gradient.to_radians(angle_mode));
+    }
+
+    /// Sets the dash pattern, as described in [`stroke_dash`](crate::P5Trait::stroke_dash).
+    pub(crate) fn set_stroke_dash(&mut self, pattern: &[f32], offset: f32) {
+        let valid = pattern.iter().all(|&l| l >= 0. && l.is_finite())
+            && pattern.iter().sum::<f32>() > 0.
+            && offset.is_finite();
+        if !pattern.is_empty() && !valid {
+            eprintln!("Warning -- `P5::stroke_dash` -- The dash pattern {:?} with the offset {} is invalid, so it's ignored. The lengths in the pattern can't be negative, and at least one of them must be greater than 0.", pattern, offset);
+            return;
+        }
+
+        self.dash = pattern.to_vec();
+        if self.dash.len() % 2 == 1 {
+            self.dash.extend_from_slice(pattern);
+        }
+        self.dash_offset = offset;
+    }
+}
+
+/// The styles saved by [`push`](crate::P5Trait::push), which haven't been restored by
+/// [`pop`](crate::P5Trait::pop) yet.
+#[derive(Clone, Default)]
+pub(crate) struct StyleStack {
+    styles: Vec<Style>,
+}
+
+impl StyleStack {
+    pub(crate) fn push(&mut self, style: &Style) {
+        self.styles.push(style.clone());
+    }
+
+    /// Records that every clip has been removed, which happens at the start of each frame, so that
+    /// popping a style that was pushed in an earlier frame doesn't restore clips that are gone.
+    pub(crate) fn remove_clips(&mut self) {
+        for style in &mut self.styles {
+            style.clip_depth = 0;
+        }
+    }
+
+    /// Restores the most recently pushed style into `style`. If there isn't one, `style` is left
+    /// unchanged, and a warning is printed, since every `pop` should be matched by a `push`.
+    pub(crate) fn pop(&mut self, style: &mut Style) {
+        match self.styles.pop() {
+            Some(saved) => *style = saved,
+            None => eprintln!("Warning -- `P5::pop` -- `pop` was called without a matching call to `push`, so there is no style to restore. Make sure every call to `pop` has a corresponding call to `push` before it."),
+        }
+    }
+}
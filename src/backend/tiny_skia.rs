@@ -1,2 +1,884 @@
-#[allow(dead_code)]
-struct TinySkiaP5 {}
+use crate::backend::clip::Clip;
+use crate::backend::path::{curve_segment, ellipse_bounds, Path, Segment};
+use crate::backend::pixels::{pixel_index, premultiply, region, Pixels};
+use crate::backend::points::PointCoverage;
+use crate::backend::shape::{add_vertex, finish_shape, Shape, Vertex};
+use crate::backend::style::{Style, StyleStack};
+use crate::backend::{image_from_pixels, save_image, Backend};
+use crate::gradient::GradientKind;
+use crate::p5::{
+    AngleMode, ArcMode, BlendMode, EllipseMode, EndMode, ImageMode, RectMode, ShapeKind, StrokeCap,
+    StrokeJoin, P5,
+};
+use crate::p5_image::{image_bounds, source_bounds};
+use crate::{ColorMode, Gradient, IntoColor, P5Image};
+use euclid::default::Box2D;
+use euclid::{point2, Transform2D};
+use std::cell::OnceCell;
+use tiny_skia::{FillRule, Mask, Paint, PathBuilder, Pixmap, Stroke};
+
+/// A structure that contains all the internal state necessary for drawing with the tiny-skia
+/// backend.
+pub struct TinySkiaP5 {
+    /// The tiny-skia [`Pixmap`](tiny_skia::Pixmap) that everything is drawn into.
+    pixmap: Pixmap,
+    /// The contents of `pixmap` converted to premultiplied ARGB, which is the format
+    /// [`get_data`](crate::P5Trait::get_data) returns. This is computed lazily, and must be
+    /// cleared whenever `pixmap` changes, which is why `pixmap` should only be modified through
+    /// [`pixmap_mut`](TinySkiaP5::pixmap_mut).
+    data: OnceCell<Vec<u32>>,
+    /// The masks applied by `end_clip`. Each one is combined with the ones before it, so only the
+    /// last one is needed to clip drawing.
+    clip_masks: Vec<Mask>,
+    /// The current drawing style, which is saved and restored by `push` and `pop`.
+    style: Style,
+    /// The styles saved by `push`.
+    styles: StyleStack,
+    /// The shape whose vertices are being recorded, if `begin_shape` has been called.
+    shape: Option<Shape>,
+    /// The clip whose shapes are being recorded, if `begin_clip` has been called.
+    clip: Option<Clip>,
+    /// The unit that angles are measured in. Like in p5.js, this isn't saved by `push`.
+    angle_mode: AngleMode,
+    /// Whether images are drawn smoothly when they're scaled. Like in p5.js, this isn't saved by
+    /// `push`.
+    smooth: bool,
+    /// The pixels copied from the canvas by `load_pixels`.
+    pixels: Pixels,
+    /// The coverage of the dots drawn by `point` and `points`.
+    point_coverage: PointCoverage,
+    /// The pixels of the dots drawn by `point` and `points`, which are kept so that drawing them
+    /// doesn't allocate a new image each time.
+    dots: Vec<u8>,
+    /// The variable frame_count contains the number of frames that have been displayed since the program started. Inside setup() the value is 0, after the first iteration of draw it is 1, etc.
+    pub frame_count: usize,
+    pub frame_rate: f32,
+    pub(crate) keys: Option<Vec<crate::Key>>,
+    /// If `Some`, contains the ASCII character value of the most recent key on the keyboard that was typed, _mostly_ respecting capitalization (please file a bug report if you find a sitaution where it doesn't). If this is `None`, that may mean that no key was pressed, or that the key is not an ascii character.
+    pub key: Option<char>,
+    /// If `Some`, contains the most recent key pressed on the keyboard as a [`Key`](crate::Key). Instead of a separate `keyIsPressed` variable, this uses an `Option`.
+    pub key_code: Option<crate::Key>,
+}
+
+impl From<crate::Color> for tiny_skia::Color {
+    fn from(c: crate::Color) -> tiny_skia::Color {
+        tiny_skia::Color::from_rgba8(c.r, c.g, c.b, c.a)
+    }
+}
+
+impl TinySkiaP5 {
+    /// Gives mutable access to the pixmap, invalidating the cached ARGB data. Also returns the mask
+    /// that drawing is clipped to, if there is one.
+    fn pixmap_mut(&mut self) -> (&mut Pixmap, Option<&Mask>) {
+        self.data.take();
+        (&mut self.pixmap, self.clip_masks.last())
+    }
+
+    /// Transforms `path` using the current transformation, and converts it into a tiny-skia path.
+    /// Returns `None` if the path is empty, since tiny-skia can't draw those.
+    fn transform_path(&self, path: &Path) -> Option<tiny_skia::Path> {
+        to_tiny_skia_path(&path.transform(&self.style.transform))
+    }
+
+    /// Creates a tiny-skia stroke from the stroke weight, cap, join and dash pattern.
+    fn tiny_skia_stroke(&self) -> Stroke {
+        Stroke {
+            width: self.style.stroke_weight,
+            miter_limit: self.style.miter_limit,
+            line_cap: match self.style.stroke_cap {
+                StrokeCap::Round => tiny_skia::LineCap::Round,
+                StrokeCap::Square => tiny_skia::LineCap::Butt,
+                StrokeCap::Project => tiny_skia::LineCap::Square,
+            },
+            line_join: match self.style.stroke_join {
+                StrokeJoin::Miter => tiny_skia::LineJoin::Miter,
+                StrokeJoin::Bevel => tiny_skia::LineJoin::Bevel,
+                StrokeJoin::Round => tiny_skia::LineJoin::Round,
+            },
+            // The pattern is validated by `stroke_dash`, so this is only `None` when it's empty.
+            dash: tiny_skia::StrokeDash::new(self.style.dash.clone(), self.style.dash_offset),
+        }
+    }
+
+    /// Draws a path correctly using the stroke weight, stroke color, fill color, etc.
+    /// attribiutes. Also transforms `path` using the current transformation before drawing.
+    fn draw_path(&mut self, path: &Path) {
+        if let Some(clip) = &mut self.clip {
+            clip.add(path, &self.style.transform);
+            return;
+        }
+
+        let path = match self.transform_path(path) {
+            Some(path) => path,
+            None => return,
+        };
+
+        if self.style.stroke_weight != 0.0 {
+            self.stroke_path(&path);
+        }
+
+        self.fill_path(&path);
+    }
+
+    /// Creates the brush that paints with `color`, or with `gradient` instead if there is one. The
+    /// gradient is transformed using the current transformation. `bounds` is the area of the
+    /// canvas that will be painted, which is only needed for conic gradients.
+    fn brush(
+        &self,
+        color: crate::Color,
+        gradient: Option<&Gradient>,
+        bounds: &Box2D<f32>,
+    ) -> Brush {
+        let gradient = match gradient {
+            Some(gradient) => gradient,
+            None => return Brush::Paint(solid_paint(color)),
+        };
+        if let Some(color) = gradient.solid_color() {
+            return Brush::Paint(solid_paint(color));
+        }
+
+        let t = &self.style.transform;
+        let transform = tiny_skia::Transform::from_row(t.m11, t.m12, t.m21, t.m22, t.m31, t.m32);
+        let stops = gradient
+            .stops
+            .iter()
+            .map(|&(position, color)| tiny_skia::GradientStop::new(position, color.into()))
+            .collect();
+
+        let shader = match gradient.kind {
+            GradientKind::Linear { x1, y1, x2, y2 } => tiny_skia::LinearGradient::new(
+                tiny_skia::Point::from_xy(x1, y1),
+                tiny_skia::Point::from_xy(x2, y2),
+                stops,
+                tiny_skia::SpreadMode::Pad,
+                transform,
+            ),
+            GradientKind::Radial { x, y, r } => tiny_skia::RadialGradient::new(
+                tiny_skia::Point::from_xy(x, y),
+                tiny_skia::Point::from_xy(x, y),
+                r,
+                stops,
+                tiny_skia::SpreadMode::Pad,
+                transform,
+            ),
+            GradientKind::Conic { .. } => {
+                let (width, height) = (self.pixmap.width() as i32, self.pixmap.height() as i32);
+                let image = match gradient.to_image(t, bounds, width, height) {
+                    Some(image) => image,
+                    None => return Brush::Paint(solid_paint(crate::Color::new(0, 0, 0, 0))),
+                };
+                let mut pattern = Pixmap::new(image.width as u32, image.height as u32)
+                    .expect("the image isn't empty");
+                for (pixel, color) in pattern.data_mut().chunks_exact_mut(4).zip(image.pixels) {
+                    pixel.copy_from_slice(&color);
+                }
+                return Brush::Pattern(pattern, image.x, image.y);
+            }
+        };
+
+        let mut paint = solid_paint(color);
+        match shader {
+            Some(shader) => paint.shader = shader,
+            // tiny-skia can't create a gradient if the transformation can't be inverted, but
+            // then there's nothing to draw anyway.
+            None => paint.set_color(tiny_skia::Color::TRANSPARENT),
+        }
+        Brush::Paint(paint)
+    }
+
+    /// Returns the paints that draw like `paint`, combining it with the canvas using
+    /// `blend_mode`. This is usually a single paint, but tiny-skia clips by fading out what's
+    /// drawn outside of the clip, which would replace the canvas there with nothing when using
+    /// `BlendMode::Replace`. So while clipped, the area is cut out of the canvas by one paint
+    /// instead, and then added into the hole by another.
+    fn blended_paints<'a>(&self, mut paint: Paint<'a>, blend_mode: BlendMode) -> Vec<Paint<'a>> {
+        paint.blend_mode = to_tiny_skia_blend_mode(blend_mode);
+        // tiny-skia's faster, less precise pipeline overflows with these blend modes.
+        paint.force_hq_pipeline = matches!(blend_mode, BlendMode::Overlay | BlendMode::HardLight);
+
+        if blend_mode == BlendMode::Replace && !self.clip_masks.is_empty() {
+            let cut = Paint {
+                blend_mode: tiny_skia::BlendMode::DestinationOut,
+                ..solid_paint(crate::Color::new(0, 0, 0, 255))
+            };
+            paint.blend_mode = tiny_skia::BlendMode::Plus;
+            vec![cut, paint]
+        } else {
+            vec![paint]
+        }
+    }
+
+    /// Strokes an already transformed `path` with the current stroke color and stroke style.
+    fn stroke_path(&mut self, path: &tiny_skia::Path) {
+        let (color, gradient) = self.style.current_stroke();
+        let margin = self.style.stroke_margin();
+        let bounds = bounds(path).inflate(margin, margin);
+        let brush = self.brush(color, gradient, &bounds);
+        let stroke = self.tiny_skia_stroke();
+        let paints = self.blended_paints(brush.paint(), self.style.current_blend_mode());
+        let (pixmap, mask) = self.pixmap_mut();
+        for paint in &paints {
+            let transform = tiny_skia::Transform::identity();
+            pixmap.stroke_path(path, paint, &stroke, transform, mask);
+        }
+    }
+
+    /// Fills an already transformed `path` with the current fill color, or with the current fill
+    /// gradient instead if there is one. Does nothing if shapes aren't filled.
+    fn fill_path(&mut self, path: &tiny_skia::Path) {
+        let (color, gradient) = match self.style.current_fill() {
+            Some(fill) => fill,
+            None => return,
+        };
+        let brush = self.brush(color, gradient, &bounds(path));
+        let paints = self.blended_paints(brush.paint(), self.style.current_blend_mode());
+        let (pixmap, mask) = self.pixmap_mut();
+        for paint in &paints {
+            let transform = tiny_skia::Transform::identity();
+            pixmap.fill_path(path, paint, FillRule::Winding, transform, mask);
+        }
+    }
+
+    /// Draws `color` over the pixels covered by the dots in `point_coverage`, combining it with
+    /// the canvas using `blend_mode`.
+    fn draw_coverage(&mut self, color: crate::Color, blend_mode: tiny_skia::BlendMode) {
+        let coverage = &self.point_coverage;
+        let size = tiny_skia::IntSize::from_wh(coverage.width as u32, coverage.height as u32);
+        let mut data = std::mem::take(&mut self.dots);
+        data.clear();
+        data.extend(coverage.colors(color).flatten());
+        let dots = match size.and_then(|size| Pixmap::from_vec(data, size)) {
+            Some(dots) => dots,
+            None => return,
+        };
+        let (x, y) = (coverage.x, coverage.y);
+        let paint = tiny_skia::PixmapPaint {
+            blend_mode,
+            ..tiny_skia::PixmapPaint::default()
+        };
+        let (pixmap, mask) = self.pixmap_mut();
+        pixmap.draw_pixmap(
+            x,
+            y,
+            dots.as_ref(),
+            &paint,
+            tiny_skia::Transform::identity(),
+            mask,
+        );
+        self.dots = dots.take();
+    }
+}
+
+/// What shapes are painted with, which is either a tiny-skia paint, or a pixmap covering the part
+/// of the canvas that's painted, with its top-left corner at the position given. tiny-skia can't
+/// draw conic gradients, so they're drawn into a pixmap instead, which has to outlive the paint
+/// that draws it.
+enum Brush {
+    Paint(Paint<'static>),
+    Pattern(Pixmap, i32, i32),
+}
+
+impl Brush {
+    fn paint(&self) -> Paint<'_> {
+        match self {
+            Brush::Paint(paint) => paint.clone(),
+            Brush::Pattern(pattern, x, y) => Paint {
+                shader: tiny_skia::Pattern::new(
+                    pattern.as_ref(),
+                    tiny_skia::SpreadMode::Pad,
+                    tiny_skia::FilterQuality::Nearest,
+                    1.,
+                    tiny_skia::Transform::from_translate(*x as f32, *y as f32),
+                ),
+                anti_alias: true,
+                ..Paint::default()
+            },
+        }
+    }
+}
+
+/// Returns the box around `path`.
+fn bounds(path: &tiny_skia::Path) -> Box2D<f32> {
+    let rect = path.bounds();
+    Box2D::new(
+        point2(rect.left(), rect.top()),
+        point2(rect.right(), rect.bottom()),
+    )
+}
+
+fn to_tiny_skia_blend_mode(mode: BlendMode) -> tiny_skia::BlendMode {
+    match mode {
+        BlendMode::Blend => tiny_skia::BlendMode::SourceOver,
+        BlendMode::Add => tiny_skia::BlendMode::Plus,
+        BlendMode::Darkest => tiny_skia::BlendMode::Darken,
+        BlendMode::Lightest => tiny_skia::BlendMode::Lighten,
+        BlendMode::Difference => tiny_skia::BlendMode::Difference,
+        BlendMode::Exclusion => tiny_skia::BlendMode::Exclusion,
+        BlendMode::Multiply => tiny_skia::BlendMode::Multiply,
+        BlendMode::Screen => tiny_skia::BlendMode::Screen,
+        BlendMode::Replace => tiny_skia::BlendMode::Source,
+        BlendMode::Remove => tiny_skia::BlendMode::DestinationOut,
+        BlendMode::Overlay => tiny_skia::BlendMode::Overlay,
+        BlendMode::HardLight => tiny_skia::BlendMode::HardLight,
+        BlendMode::SoftLight => tiny_skia::BlendMode::SoftLight,
+        BlendMode::Dodge => tiny_skia::BlendMode::ColorDodge,
+        BlendMode::Burn => tiny_skia::BlendMode::ColorBurn,
+    }
+}
+
+fn solid_paint<'a>(color: crate::Color) -> Paint<'a> {
+    let mut paint = Paint::default();
+    paint.set_color(color.into());
+    paint.anti_alias = true;
+    paint
+}
+
+fn to_tiny_skia_path(path: &Path) -> Option<tiny_skia::Path> {
+    let mut pb = PathBuilder::new();
+    let (mut start, mut current) = (point2(0., 0.), point2(0., 0.));
+    for segment in &path.segments {
+        match *segment {
+            Segment::MoveTo(p) => {
+                pb.move_to(p.x, p.y);
+                start = p;
+            }
+            Segment::LineTo(p) => pb.line_to(p.x, p.y),
+            Segment::QuadTo(c, p) => pb.quad_to(c.x, c.y, p.x, p.y),
+            Segment::CubicTo(c1, c2, p) => pb.cubic_to(c1.x, c1.y, c2.x, c2.y, p.x, p.y),
+            Segment::Close => {
+                // tiny-skia offsets the implicit closing line of a contour when it's stroked with
+                // round or square caps, so the closing line is always added explicitly.
+                if current != start {
+                    pb.line_to(start.x, start.y);
+                }
+                pb.close();
+            }
+        }
+        current = match *segment {
+            Segment::MoveTo(p)
+            | Segment::LineTo(p)
+            | Segment::QuadTo(_, p)
+            | Segment::CubicTo(_, _, p) => p,
+            Segment::Close => start,
+        };
+    }
+    pb.finish()
+}
+
+impl Backend for TinySkiaP5 {
+    fn new(width: usize, height: usize) -> TinySkiaP5 {
+        TinySkiaP5 {
+            pixmap: Pixmap::new(width as u32, height as u32)
+                .expect("The canvas's width and height must be non-zero"),
+            data: OnceCell::new(),
+            clip_masks: Vec::new(),
+            style: Style::new(),
+            styles: StyleStack::default(),
+            shape: None,
+            clip: None,
+            angle_mode: AngleMode::Radians,
+            smooth: true,
+            pixels: Pixels::default(),
+            point_coverage: PointCoverage::default(),
+            dots: Vec::new(),
+            frame_count: 0,
+            // TODO: p5js docs say the default framerate is based on the monitor refresh rate, but we hard code it to be 60.
+            frame_rate: 60.,
+            keys: None,
+            key: None,
+            key_code: None,
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.frame_count += 1;
+        self.reset_matrix();
+        self.clip_masks.clear();
+        self.style.clip_depth = 0;
+        self.styles.remove_clips();
+    }
+
+    fn set_keys(&mut self, keys: Option<Vec<crate::Key>>) {
+        if let Some(key) = keys.as_ref().and_then(|keys| keys.last()) {
+            self.key_code = Some(*key);
+        }
+        self.keys = keys;
+    }
+
+    fn target_frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    /// Copies the contents of the pixmap into an image, converting tiny-skia's premultiplied
+    /// pixels into straight RGBA.
+    fn to_image(&self) -> image::RgbaImage {
+        let pixels = self.pixmap.pixels().iter().map(|pixel| {
+            let c = pixel.demultiply();
+            [c.red(), c.green(), c.blue(), c.alpha()]
+        });
+        image_from_pixels(self.pixmap.width(), self.pixmap.height(), pixels)
+    }
+}
+
+impl P5 for TinySkiaP5 {
+    fn background<C: IntoColor>(&mut self, c: C) {
+        let c = c.into_color(self.style.color_mode);
+        if self.clip_masks.is_empty() {
+            self.pixmap_mut().0.fill(c.into());
+            return;
+        }
+
+        // Only the area inside of the clip is covered, like when drawing anything else.
+        let paints = self.blended_paints(solid_paint(c), BlendMode::Replace);
+        let (pixmap, mask) = self.pixmap_mut();
+        let (w, h) = (pixmap.width() as f32, pixmap.height() as f32);
+        let rect = tiny_skia::Rect::from_xywh(0., 0., w, h)
+            .expect("The canvas's width and height must be non-zero");
+        for paint in &paints {
+            pixmap.fill_rect(rect, paint, tiny_skia::Transform::identity(), mask);
+        }
+    }
+
+    fn ellipse(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let (x, y, w, h) = ellipse_bounds(self.style.ellipse_mode, x, y, w, h);
+        self.draw_path(&Path::ellipse(x, y, w, h));
+    }
+
+    fn circle(&mut self, x: f32, y: f32, d: f32) {
+        self.ellipse(x, y, d, d);
+    }
+
+    fn ellipse_mode(&mut self, mode: EllipseMode) {
+        self.style.ellipse_mode = mode;
+    }
+
+    fn arc(&mut self, x: f32, y: f32, w: f32, h: f32, start: f32, stop: f32, mode: ArcMode) {
+        let (x, y, w, h) = ellipse_bounds(self.style.ellipse_mode, x, y, w, h);
+        let (start, stop) = (
+            self.angle_mode.to_radians(start),
+            self.angle_mode.to_radians(stop),
+        );
+        self.draw_path(&Path::arc(x, y, w, h, start, stop, mode));
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        if self.clip.is_some() {
+            return;
+        }
+        if self.style.stroke_weight != 0. {
+            let path = match self.transform_path(&Path::line(x1, y1, x2, y2)) {
+                Some(path) => path,
+                None => return,
+            };
+
+            self.stroke_path(&path);
+        } else {
+            eprintln!("Warning -- `P5::line` -- `stroke_weight` is 0., so calling `line`  doesn't do anything. Consider calling `P5::stroke_weight` with a non-zero stroke weight.");
+        }
+    }
+
+    fn point(&mut self, x: f32, y: f32) {
+        self.points(&[(x, y)]);
+    }
+
+    fn points(&mut self, points: &[(f32, f32)]) {
+        if self.style.stroke_weight == 0. || self.clip.is_some() {
+            return;
+        }
+
+        let size = (self.pixmap.width() as usize, self.pixmap.height() as usize);
+        let weight = self.style.stroke_weight;
+        if !self
+            .point_coverage
+            .update(points, &self.style.transform, weight, size)
+        {
+            return;
+        }
+
+        let (color, _) = self.style.current_stroke();
+        let blend_mode = self.style.current_blend_mode();
+        if blend_mode == BlendMode::Replace {
+            // The pixels around the dots are transparent, and would replace the canvas too, so the
+            // dots are cut out of the canvas first, and then added into the holes.
+            let opaque = crate::Color::new(0, 0, 0, 255);
+            self.draw_coverage(opaque, tiny_skia::BlendMode::DestinationOut);
+            self.draw_coverage(color, tiny_skia::BlendMode::Plus);
+        } else {
+            self.draw_coverage(color, to_tiny_skia_blend_mode(blend_mode));
+        }
+    }
+
+    fn stroke_weight(&mut self, weight: f32) {
+        self.style.stroke_weight = weight;
+    }
+
+    fn no_stroke(&mut self) {
+        self.style.stroke_weight = 0.0;
+    }
+
+    fn stroke<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_stroke(color);
+    }
+
+    fn stroke_gradient(&mut self, gradient: &Gradient) {
+        self.style.set_stroke_gradient(gradient, self.angle_mode);
+    }
+
+    fn stroke_cap(&mut self, cap: StrokeCap) {
+        self.style.stroke_cap = cap;
+    }
+
+    fn stroke_join(&mut self, join: StrokeJoin) {
+        self.style.stroke_join = join;
+    }
+
+    fn miter_limit(&mut self, limit: f32) {
+        self.style.miter_limit = limit;
+    }
+
+    fn stroke_dash(&mut self, pattern: &[f32], offset: f32) {
+        self.style.set_stroke_dash(pattern, offset);
+    }
+
+    fn fill<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_fill(Some(color));
+    }
+
+    fn fill_gradient(&mut self, gradient: &Gradient) {
+        self.style.set_fill_gradient(gradient, self.angle_mode);
+    }
+
+    fn quad(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        self.draw_path(&Path::polygon(&[(x1, y1), (x2, y2), (x3, y3), (x4, y4)]));
+    }
+
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, rounding: Option<crate::p5::RectRounding>) {
+        let path = Path::rect(self.style.rect_mode, x, y, w, h, rounding.as_ref());
+        self.draw_path(&path);
+    }
+
+    fn rect_mode(&mut self, mode: RectMode) {
+        self.style.rect_mode = mode;
+    }
+
+    fn triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
+        self.draw_path(&Path::polygon(&[(x1, y1), (x2, y2), (x3, y3)]));
+    }
+
+    fn begin_shape(&mut self, kind: ShapeKind) {
+        self.shape = Some(Shape::new(kind));
+    }
+
+    fn vertex(&mut self, x: f32, y: f32) {
+        add_vertex(&mut self.shape, Vertex::Line(x, y), "vertex");
+    }
+
+    fn end_shape(&mut self, mode: EndMode) {
+        let (points, paths) = finish_shape(&mut self.shape, mode, self.style.curve_tightness);
+        if !points.is_empty() {
+            self.points(&points);
+        }
+        for path in paths {
+            self.draw_path(&path);
+        }
+    }
+
+    fn bezier_vertex(&mut self, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        let vertex = Vertex::bezier(x2, y2, x3, y3, x4, y4);
+        add_vertex(&mut self.shape, vertex, "bezier_vertex");
+    }
+
+    fn quadratic_vertex(&mut self, cx: f32, cy: f32, x3: f32, y3: f32) {
+        let vertex = Vertex::quadratic(cx, cy, x3, y3);
+        add_vertex(&mut self.shape, vertex, "quadratic_vertex");
+    }
+
+    fn curve_vertex(&mut self, x: f32, y: f32) {
+        add_vertex(&mut self.shape, Vertex::Curve(x, y), "curve_vertex");
+    }
+
+    fn bezier(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        self.draw_path(&Path::bezier(x1, y1, x2, y2, x3, y3, x4, y4));
+    }
+
+    fn curve(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        let points = [(x1, y1), (x2, y2), (x3, y3), (x4, y4)];
+        self.draw_path(&Path::curve(points, self.style.curve_tightness));
+    }
+
+    fn curve_tightness(&mut self, amount: f32) {
+        self.style.curve_tightness = amount;
+    }
+
+    fn curve_point(&self, a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+        let points = [(a, 0.), (b, 0.), (c, 0.), (d, 0.)];
+        curve_segment(points, self.style.curve_tightness).x(t)
+    }
+
+    fn reset_matrix(&mut self) {
+        self.style.transform = Transform2D::identity();
+    }
+
+    fn apply_matrix(&mut self, m11: f32, m12: f32, m21: f32, m22: f32, m31: f32, m32: f32) {
+        let matrix = Transform2D::new(m11, m12, m21, m22, m31, m32);
+        self.style.apply_matrix(&matrix);
+    }
+
+    fn push(&mut self) {
+        self.styles.push(&self.style);
+    }
+
+    fn pop(&mut self) {
+        self.styles.pop(&mut self.style);
+        self.clip_masks.truncate(self.style.clip_depth);
+    }
+
+    fn begin_clip(&mut self, invert: bool) {
+        self.clip = Some(Clip::new(invert));
+    }
+
+    fn end_clip(&mut self) {
+        let clip = match self.clip.take() {
+            Some(clip) => clip,
+            None => {
+                eprintln!("Warning -- `P5::end_clip` -- `end_clip` was called without a matching call to `begin_clip`, so there is no clip to apply.");
+                return;
+            }
+        };
+
+        let mut mask = Mask::new(self.pixmap.width(), self.pixmap.height())
+            .expect("The canvas's width and height must be non-zero");
+        if let Some(path) = to_tiny_skia_path(&clip.union()) {
+            let transform = tiny_skia::Transform::identity();
+            mask.fill_path(&path, FillRule::Winding, true, transform);
+        }
+        if clip.invert {
+            mask.invert();
+        }
+        if let Some(previous) = self.clip_masks.last() {
+            for (coverage, &previous) in mask.data_mut().iter_mut().zip(previous.data()) {
+                *coverage = ((u16::from(*coverage) * u16::from(previous) + 127) / 255) as u8;
+            }
+        }
+        self.clip_masks.push(mask);
+        self.style.clip_depth += 1;
+    }
+
+    fn no_fill(&mut self) {
+        self.style.set_fill(None);
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode) {
+        self.style.blend_mode = mode;
+    }
+
+    fn erase(&mut self, fill_strength: f32, stroke_strength: f32) {
+        self.style.set_erase(Some((fill_strength, stroke_strength)));
+    }
+
+    fn no_erase(&mut self) {
+        self.style.set_erase(None);
+    }
+
+    fn frame_rate(&mut self, fps: f32) {
+        self.frame_rate = fps;
+    }
+
+    fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    fn color_mode(&mut self, mode: ColorMode) {
+        self.style.color_mode = mode;
+    }
+
+    fn angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    fn get_angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    fn key_is_down(&self, key: crate::Key) -> bool {
+        // TODO: Instead of calling `contains`, directly use the `window.is_key_down`
+        // function
+        self.keys.as_ref().is_some_and(|keys| keys.contains(&key))
+    }
+
+    fn text(&mut self, s: &str, x: f32, y: f32) {
+        if let Some(clip) = &mut self.clip {
+            let path = Path::text(&self.style.font, self.style.text_size, s, x, y);
+            clip.add(&path, &self.style.transform);
+            return;
+        }
+        if self.style.fill_color.is_some() {
+            let path = Path::text(&self.style.font, self.style.text_size, s, x, y);
+            if let Some(path) = self.transform_path(&path) {
+                self.fill_path(&path);
+            }
+        }
+    }
+
+    fn text_size(&mut self, size: f32) {
+        self.style.text_size = size;
+    }
+
+    fn text_font(&mut self, family_name: &str) {
+        self.style.font = crate::backend::load_font(family_name);
+    }
+
+    fn image_region(
+        &mut self,
+        img: &P5Image,
+        dx: f32,
+        dy: f32,
+        dw: f32,
+        dh: f32,
+        sx: f32,
+        sy: f32,
+        sw: f32,
+        sh: f32,
+    ) {
+        if self.clip.is_some() {
+            return;
+        }
+        let dest = image_bounds(self.style.image_mode, dx, dy, dw, dh);
+        let (dest, source) = match img.placement(dest, source_bounds(sx, sy, sw, sh)) {
+            Some(placement) => placement,
+            None => return,
+        };
+
+        let data = img.tinted_data(self.style.tint);
+        let pixmap = match tiny_skia::PixmapRef::from_bytes(&data, img.width(), img.height()) {
+            Some(pixmap) => pixmap,
+            None => return,
+        };
+        let quality = if self.smooth {
+            tiny_skia::FilterQuality::Bilinear
+        } else {
+            tiny_skia::FilterQuality::Nearest
+        };
+        let scale_x = dest.size.width / source.size.width;
+        let scale_y = dest.size.height / source.size.height;
+        let paint = Paint {
+            shader: tiny_skia::Pattern::new(
+                pixmap,
+                tiny_skia::SpreadMode::Pad,
+                quality,
+                1.,
+                tiny_skia::Transform::from_row(
+                    scale_x,
+                    0.,
+                    0.,
+                    scale_y,
+                    dest.origin.x - source.origin.x * scale_x,
+                    dest.origin.y - source.origin.y * scale_y,
+                ),
+            ),
+            anti_alias: true,
+            ..Paint::default()
+        };
+        let rect = match tiny_skia::Rect::from_xywh(
+            dest.origin.x,
+            dest.origin.y,
+            dest.size.width,
+            dest.size.height,
+        ) {
+            Some(rect) => rect,
+            None => return,
+        };
+
+        let t = &self.style.transform;
+        let transform = tiny_skia::Transform::from_row(t.m11, t.m12, t.m21, t.m22, t.m31, t.m32);
+        let paints = self.blended_paints(paint, self.style.current_blend_mode());
+        let (pixmap, mask) = self.pixmap_mut();
+        for paint in &paints {
+            pixmap.fill_rect(rect, paint, transform, mask);
+        }
+    }
+
+    fn image_mode(&mut self, mode: ImageMode) {
+        self.style.image_mode = mode;
+    }
+
+    fn tint<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_tint(Some(color));
+    }
+
+    fn no_tint(&mut self) {
+        self.style.set_tint(None);
+    }
+
+    fn smooth(&mut self) {
+        self.smooth = true;
+    }
+
+    fn no_smooth(&mut self) {
+        self.smooth = false;
+    }
+
+    fn get_data(&self) -> &[u32] {
+        self.data.get_or_init(|| {
+            self.pixmap
+                .pixels()
+                .iter()
+                .map(|p| u32::from_be_bytes([p.alpha(), p.red(), p.green(), p.blue()]))
+                .collect()
+        })
+    }
+
+    fn load_pixels(&mut self) {
+        let canvas = self.to_image();
+        self.pixels.load(canvas);
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        self.pixels.data_mut()
+    }
+
+    fn update_pixels(&mut self) {
+        let pixels = std::mem::take(&mut self.pixels);
+        if let Some(data) = pixels.to_update() {
+            let (pixmap, _) = self.pixmap_mut();
+            for (pixel, rgba) in pixmap
+                .data_mut()
+                .chunks_exact_mut(4)
+                .zip(data.chunks_exact(4))
+            {
+                pixel.copy_from_slice(&premultiply(rgba));
+            }
+        }
+        self.pixels = pixels;
+    }
+
+    fn get(&self, x: f32, y: f32) -> crate::Color {
+        let (width, height) = (self.pixmap.width() as usize, self.pixmap.height() as usize);
+        match pixel_index(x, y, width, height) {
+            Some(i) => {
+                let c = self.pixmap.pixels()[i].demultiply();
+                crate::Color::new(c.red(), c.green(), c.blue(), c.alpha())
+            }
+            None => crate::Color::new(0, 0, 0, 0),
+        }
+    }
+
+    fn get_region(&self, x: f32, y: f32, w: f32, h: f32) -> P5Image {
+        region(&self.to_image(), x, y, w, h)
+    }
+
+    fn set<C: IntoColor>(&mut self, x: f32, y: f32, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        if !self.pixels.is_loaded() {
+            self.load_pixels();
+        }
+        self.pixels.set(x, y, color);
+    }
+
+    fn save_canvas<P: AsRef<std::path::Path>>(&self, path: P) -> image::ImageResult<()> {
+        save_image(self.to_image(), path)
+    }
+}
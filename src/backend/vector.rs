@@ -0,0 +1,602 @@
+use crate::backend::path::{curve_segment, ellipse_bounds, Path};
+use crate::backend::pixels::{region, Pixels};
+use crate::backend::shape::{add_vertex, finish_shape, Shape, Vertex};
+use crate::backend::style::{Style, StyleStack};
+use crate::backend::Backend;
+use crate::p5::{
+    AngleMode, ArcMode, BlendMode, EllipseMode, EndMode, ImageMode, RectMode, ShapeKind, StrokeCap,
+    StrokeJoin, P5,
+};
+use crate::{Color, ColorMode, Gradient, IntoColor, P5Image};
+use euclid::default::Transform2D;
+use font_kit::font::Font;
+use std::collections::HashSet;
+
+pub(crate) mod pdf;
+pub(crate) mod svg;
+
+/// A file format that a [`VectorP5`](crate::VectorP5) can save its recording as.
+pub trait VectorFormat: Default {
+    /// Writes everything that has been drawn on the `canvas` to `path`.
+    fn save(&self, canvas: &Canvas, path: &std::path::Path) -> std::io::Result<()>;
+
+    /// Called with the finished `canvas` at the end of every frame of `draw`, when the sketch is
+    /// rendered without a window, as described in [`end_frame`](crate::Backend::end_frame).
+    /// Formats that can hold more than one frame can keep a copy of it here. By default, this does
+    /// nothing.
+    fn end_frame(&mut self, _canvas: &Canvas) {}
+}
+
+/// Everything that has been drawn on the canvas of a [`VectorP5`](crate::VectorP5).
+#[derive(Clone)]
+pub struct Canvas {
+    /// The recorded drawing operations, from bottom to top.
+    pub(crate) elements: Vec<Element>,
+    /// How many times `background` has cleared the canvas. In between, elements are only ever
+    /// added to the end, so formats that keep earlier frames only need to copy the new ones.
+    pub(crate) clears: usize,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+}
+
+/// The stroke used to outline a recorded shape.
+#[derive(Debug, Clone)]
+pub(crate) struct Stroke {
+    pub(crate) color: Color,
+    pub(crate) weight: f32,
+    pub(crate) cap: StrokeCap,
+    pub(crate) join: StrokeJoin,
+    pub(crate) miter_limit: f32,
+    /// The lengths of the dashes and gaps, which is empty for a solid stroke.
+    pub(crate) dash: Vec<f32>,
+    pub(crate) dash_offset: f32,
+}
+
+impl Stroke {
+    /// Creates the stroke that `style` draws with.
+    fn new(style: &Style) -> Stroke {
+        Stroke {
+            color: style.stroke_color,
+            weight: style.stroke_weight,
+            cap: style.stroke_cap,
+            join: style.stroke_join,
+            miter_limit: style.miter_limit,
+            dash: style.dash.clone(),
+            dash_offset: style.dash_offset,
+        }
+    }
+}
+
+/// A single drawing operation recorded by a [`VectorP5`](crate::VectorP5). Everything is stored
+/// in canvas coordinates, with the current transformation already applied, except for text, which
+/// keeps the transformation it was drawn with.
+#[derive(Clone)]
+pub(crate) enum Element {
+    Path {
+        path: Path,
+        fill: Option<Color>,
+        stroke: Option<Stroke>,
+    },
+    Text {
+        text: String,
+        font: Font,
+        size: f32,
+        x: f32,
+        y: f32,
+        transform: Transform2D<f32>,
+        fill: Color,
+    },
+}
+
+/// A backend which, instead of drawing pixels, records the shapes that are drawn as vector paths,
+/// so they can be saved to a vector format `F` with [`save_canvas`](crate::P5Trait::save_canvas).
+/// This is useful for print and pen-plotter work, where the output needs to be scaled without
+/// losing quality.
+///
+/// Since there are no pixels, [`get_data`](crate::P5Trait::get_data),
+/// [`to_image`](crate::Backend::to_image) and the functions for reading pixels, like
+/// [`get`](crate::P5Trait::get), only return a blank canvas.
+pub struct VectorP5<F: VectorFormat> {
+    /// Everything that has been drawn so far.
+    canvas: Canvas,
+    /// The format that the canvas is saved as.
+    format: F,
+    /// A blank canvas returned by `get_data`, since there are no pixels to return.
+    blank: Vec<u32>,
+    /// The pixels returned by `pixels_mut`, which are transparent after `load_pixels` is called,
+    /// since there are no pixels to load.
+    pixels: Pixels,
+    /// The current drawing style, which is saved and restored by `push` and `pop`.
+    style: Style,
+    /// The styles saved by `push`.
+    styles: StyleStack,
+    /// The shape whose vertices are being recorded, if `begin_shape` has been called.
+    shape: Option<Shape>,
+    /// Whether shapes are being drawn between `begin_clip` and `end_clip`, in which case they
+    /// aren't recorded.
+    clipping: bool,
+    /// The unit that angles are measured in. Like in p5.js, this isn't saved by `push`.
+    angle_mode: AngleMode,
+    /// The functions that have already warned that what they do can't be recorded, so that each
+    /// warning is only printed once, instead of every frame.
+    warned: HashSet<&'static str>,
+    /// The variable frame_count contains the number of frames that have been displayed since the program started. Inside setup() the value is 0, after the first iteration of draw it is 1, etc.
+    pub frame_count: usize,
+    pub frame_rate: f32,
+    pub(crate) keys: Option<Vec<crate::Key>>,
+    /// If `Some`, contains the ASCII character value of the most recent key on the keyboard that was typed, _mostly_ respecting capitalization (please file a bug report if you find a sitaution where it doesn't). If this is `None`, that may mean that no key was pressed, or that the key is not an ascii character.
+    pub key: Option<char>,
+    /// If `Some`, contains the most recent key pressed on the keyboard as a [`Key`](crate::Key). Instead of a separate `keyIsPressed` variable, this uses an `Option`.
+    pub key_code: Option<crate::Key>,
+}
+
+impl<F: VectorFormat> VectorP5<F> {
+    /// Whether shapes are recorded, which they aren't while clipping or erasing, since neither
+    /// can be recorded yet.
+    fn records_shapes(&self) -> bool {
+        !self.clipping && self.style.erase.is_none()
+    }
+
+    /// Prints the warning `message` from the P5 function called `function`, unless it has already
+    /// been printed.
+    fn warn_once(&mut self, function: &'static str, message: &str) {
+        if self.warned.insert(function) {
+            eprintln!("Warning -- `P5::{}` -- {}", function, message);
+        }
+    }
+
+    /// Records `path` with the current fill and stroke, after transforming it using the current
+    /// transformation.
+    fn draw_path(&mut self, path: &Path) {
+        if !self.records_shapes() {
+            return;
+        }
+
+        let stroke = if self.style.stroke_weight != 0. {
+            Some(Stroke::new(&self.style))
+        } else {
+            None
+        };
+
+        if self.style.fill_color.is_none() && stroke.is_none() {
+            return;
+        }
+
+        self.canvas.elements.push(Element::Path {
+            path: path.transform(&self.style.transform),
+            fill: self.style.fill_color,
+            stroke,
+        });
+    }
+}
+
+impl<F: VectorFormat> Backend for VectorP5<F> {
+    fn new(width: usize, height: usize) -> VectorP5<F> {
+        VectorP5 {
+            canvas: Canvas {
+                elements: Vec::new(),
+                clears: 0,
+                width,
+                height,
+            },
+            format: F::default(),
+            blank: vec![0; width * height],
+            pixels: Pixels::default(),
+            style: Style::new(),
+            styles: StyleStack::default(),
+            shape: None,
+            clipping: false,
+            angle_mode: AngleMode::Radians,
+            warned: HashSet::new(),
+            frame_count: 0,
+            // TODO: p5js docs say the default framerate is based on the monitor refresh rate, but we hard code it to be 60.
+            frame_rate: 60.,
+            keys: None,
+            key: None,
+            key_code: None,
+        }
+    }
+
+    fn begin_frame(&mut self) {
+        self.frame_count += 1;
+        self.reset_matrix();
+    }
+
+    fn end_frame(&mut self) {
+        self.format.end_frame(&self.canvas);
+    }
+
+    fn set_keys(&mut self, keys: Option<Vec<crate::Key>>) {
+        if let Some(key) = keys.as_ref().and_then(|keys| keys.last()) {
+            self.key_code = Some(*key);
+        }
+        self.keys = keys;
+    }
+
+    fn target_frame_rate(&self) -> f32 {
+        self.frame_rate
+    }
+
+    fn to_image(&self) -> image::RgbaImage {
+        image::RgbaImage::new(self.canvas.width as u32, self.canvas.height as u32)
+    }
+}
+
+impl<F: VectorFormat> P5 for VectorP5<F> {
+    /// Since the background covers everything that was drawn before it, this discards everything
+    /// that has been recorded so far, and replaces it with a rectangle covering the canvas.
+    fn background<C: IntoColor>(&mut self, c: C) {
+        let (w, h) = (self.canvas.width as f32, self.canvas.height as f32);
+        self.canvas.elements.clear();
+        self.canvas.clears += 1;
+        self.canvas.elements.push(Element::Path {
+            path: Path::polygon(&[(0., 0.), (w, 0.), (w, h), (0., h)]),
+            fill: Some(c.into_color(self.style.color_mode)),
+            stroke: None,
+        });
+    }
+
+    fn ellipse(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let (x, y, w, h) = ellipse_bounds(self.style.ellipse_mode, x, y, w, h);
+        self.draw_path(&Path::ellipse(x, y, w, h));
+    }
+
+    fn circle(&mut self, x: f32, y: f32, d: f32) {
+        self.ellipse(x, y, d, d);
+    }
+
+    fn ellipse_mode(&mut self, mode: EllipseMode) {
+        self.style.ellipse_mode = mode;
+    }
+
+    fn arc(&mut self, x: f32, y: f32, w: f32, h: f32, start: f32, stop: f32, mode: ArcMode) {
+        let (x, y, w, h) = ellipse_bounds(self.style.ellipse_mode, x, y, w, h);
+        let (start, stop) = (
+            self.angle_mode.to_radians(start),
+            self.angle_mode.to_radians(stop),
+        );
+        self.draw_path(&Path::arc(x, y, w, h, start, stop, mode));
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32) {
+        if !self.records_shapes() {
+            return;
+        }
+        if self.style.stroke_weight != 0. {
+            self.canvas.elements.push(Element::Path {
+                path: Path::line(x1, y1, x2, y2).transform(&self.style.transform),
+                fill: None,
+                stroke: Some(Stroke::new(&self.style)),
+            });
+        } else {
+            eprintln!("Warning -- `P5::line` -- `stroke_weight` is 0., so calling `line`  doesn't do anything. Consider calling `P5::stroke_weight` with a non-zero stroke weight.");
+        }
+    }
+
+    /// Points are recorded as circles with a diameter of the stroke weight, filled with the
+    /// stroke color.
+    fn point(&mut self, x: f32, y: f32) {
+        if self.style.stroke_weight == 0. || !self.records_shapes() {
+            return;
+        }
+
+        let p = self.style.transform.transform_point(euclid::point2(x, y));
+        let weight = self.style.stroke_weight;
+        self.canvas.elements.push(Element::Path {
+            path: Path::ellipse(p.x, p.y, weight, weight),
+            fill: Some(self.style.stroke_color),
+            stroke: None,
+        });
+    }
+
+    fn stroke_weight(&mut self, weight: f32) {
+        self.style.stroke_weight = weight;
+    }
+
+    fn no_stroke(&mut self) {
+        self.style.stroke_weight = 0.0;
+    }
+
+    fn stroke<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_stroke(color);
+    }
+
+    /// Gradients aren't recorded yet, so this strokes with the color of the gradient's first stop
+    /// instead.
+    fn stroke_gradient(&mut self, gradient: &Gradient) {
+        self.warn_once(
+            "stroke_gradient",
+            "Gradients can't be recorded by vector backends yet, so the color of the first stop is used instead.",
+        );
+        self.style.set_stroke_gradient(gradient, self.angle_mode);
+    }
+
+    fn stroke_cap(&mut self, cap: StrokeCap) {
+        self.style.stroke_cap = cap;
+    }
+
+    fn stroke_join(&mut self, join: StrokeJoin) {
+        self.style.stroke_join = join;
+    }
+
+    fn miter_limit(&mut self, limit: f32) {
+        self.style.miter_limit = limit;
+    }
+
+    fn stroke_dash(&mut self, pattern: &[f32], offset: f32) {
+        self.style.set_stroke_dash(pattern, offset);
+    }
+
+    fn fill<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_fill(Some(color));
+    }
+
+    /// Gradients aren't recorded yet, so this fills with the color of the gradient's first stop
+    /// instead.
+    fn fill_gradient(&mut self, gradient: &Gradient) {
+        self.warn_once(
+            "fill_gradient",
+            "Gradients can't be recorded by vector backends yet, so the color of the first stop is used instead.",
+        );
+        self.style.set_fill_gradient(gradient, self.angle_mode);
+    }
+
+    fn quad(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        self.draw_path(&Path::polygon(&[(x1, y1), (x2, y2), (x3, y3), (x4, y4)]));
+    }
+
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, rounding: Option<crate::p5::RectRounding>) {
+        let path = Path::rect(self.style.rect_mode, x, y, w, h, rounding.as_ref());
+        self.draw_path(&path);
+    }
+
+    fn rect_mode(&mut self, mode: RectMode) {
+        self.style.rect_mode = mode;
+    }
+
+    fn triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
+        self.draw_path(&Path::polygon(&[(x1, y1), (x2, y2), (x3, y3)]));
+    }
+
+    fn begin_shape(&mut self, kind: ShapeKind) {
+        self.shape = Some(Shape::new(kind));
+    }
+
+    fn vertex(&mut self, x: f32, y: f32) {
+        add_vertex(&mut self.shape, Vertex::Line(x, y), "vertex");
+    }
+
+    fn end_shape(&mut self, mode: EndMode) {
+        let (points, paths) = finish_shape(&mut self.shape, mode, self.style.curve_tightness);
+        if !points.is_empty() {
+            self.points(&points);
+        }
+        for path in paths {
+            self.draw_path(&path);
+        }
+    }
+
+    fn bezier_vertex(&mut self, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        let vertex = Vertex::bezier(x2, y2, x3, y3, x4, y4);
+        add_vertex(&mut self.shape, vertex, "bezier_vertex");
+    }
+
+    fn quadratic_vertex(&mut self, cx: f32, cy: f32, x3: f32, y3: f32) {
+        let vertex = Vertex::quadratic(cx, cy, x3, y3);
+        add_vertex(&mut self.shape, vertex, "quadratic_vertex");
+    }
+
+    fn curve_vertex(&mut self, x: f32, y: f32) {
+        add_vertex(&mut self.shape, Vertex::Curve(x, y), "curve_vertex");
+    }
+
+    fn bezier(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        self.draw_path(&Path::bezier(x1, y1, x2, y2, x3, y3, x4, y4));
+    }
+
+    fn curve(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32) {
+        let points = [(x1, y1), (x2, y2), (x3, y3), (x4, y4)];
+        self.draw_path(&Path::curve(points, self.style.curve_tightness));
+    }
+
+    fn curve_tightness(&mut self, amount: f32) {
+        self.style.curve_tightness = amount;
+    }
+
+    fn curve_point(&self, a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+        let points = [(a, 0.), (b, 0.), (c, 0.), (d, 0.)];
+        curve_segment(points, self.style.curve_tightness).x(t)
+    }
+
+    fn reset_matrix(&mut self) {
+        self.style.transform = Transform2D::identity();
+    }
+
+    fn apply_matrix(&mut self, m11: f32, m12: f32, m21: f32, m22: f32, m31: f32, m32: f32) {
+        let matrix = Transform2D::new(m11, m12, m21, m22, m31, m32);
+        self.style.apply_matrix(&matrix);
+    }
+
+    fn push(&mut self) {
+        self.styles.push(&self.style);
+    }
+
+    fn pop(&mut self) {
+        self.styles.pop(&mut self.style);
+    }
+
+    fn begin_clip(&mut self, _invert: bool) {
+        self.warn_once(
+            "begin_clip",
+            "Clipping can't be recorded by vector backends yet, so the shapes drawn before `end_clip` are left out, and nothing is clipped.",
+        );
+        self.clipping = true;
+    }
+
+    fn end_clip(&mut self) {
+        if !self.clipping {
+            eprintln!("Warning -- `P5::end_clip` -- `end_clip` was called without a matching call to `begin_clip`, so there is no clip to apply.");
+        }
+        self.clipping = false;
+    }
+
+    fn no_fill(&mut self) {
+        self.style.set_fill(None);
+    }
+
+    fn blend_mode(&mut self, mode: BlendMode) {
+        if mode != BlendMode::Blend {
+            self.warn_once(
+                "blend_mode",
+                "Blend modes can't be recorded by vector backends yet, so shapes are still drawn over each other normally.",
+            );
+        }
+        self.style.blend_mode = mode;
+    }
+
+    fn erase(&mut self, fill_strength: f32, stroke_strength: f32) {
+        self.warn_once(
+            "erase",
+            "Erasing can't be recorded by vector backends yet, so the shapes drawn before `no_erase` are left out.",
+        );
+        self.style.set_erase(Some((fill_strength, stroke_strength)));
+    }
+
+    fn no_erase(&mut self) {
+        self.style.set_erase(None);
+    }
+
+    fn frame_rate(&mut self, fps: f32) {
+        self.frame_rate = fps;
+    }
+
+    fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    fn color_mode(&mut self, mode: ColorMode) {
+        self.style.color_mode = mode;
+    }
+
+    fn angle_mode(&mut self, mode: AngleMode) {
+        self.angle_mode = mode;
+    }
+
+    fn get_angle_mode(&self) -> AngleMode {
+        self.angle_mode
+    }
+
+    fn key_is_down(&self, key: crate::Key) -> bool {
+        self.keys.as_ref().is_some_and(|keys| keys.contains(&key))
+    }
+
+    fn text(&mut self, s: &str, x: f32, y: f32) {
+        if !self.records_shapes() {
+            return;
+        }
+        if let Some(fill_color) = self.style.fill_color {
+            self.canvas.elements.push(Element::Text {
+                text: s.to_owned(),
+                font: self.style.font.clone(),
+                size: self.style.text_size,
+                x,
+                y,
+                transform: self.style.transform,
+                fill: fill_color,
+            });
+        }
+    }
+
+    fn text_size(&mut self, size: f32) {
+        self.style.text_size = size;
+    }
+
+    fn text_font(&mut self, family_name: &str) {
+        self.style.font = crate::backend::load_font(family_name);
+    }
+
+    fn image_region(
+        &mut self,
+        _img: &P5Image,
+        _dx: f32,
+        _dy: f32,
+        _dw: f32,
+        _dh: f32,
+        _sx: f32,
+        _sy: f32,
+        _sw: f32,
+        _sh: f32,
+    ) {
+        self.warn_once(
+            "image",
+            "Images can't be recorded by vector backends yet, so they're left out.",
+        );
+    }
+
+    fn image_mode(&mut self, mode: ImageMode) {
+        self.style.image_mode = mode;
+    }
+
+    fn tint<C: IntoColor>(&mut self, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        self.style.set_tint(Some(color));
+    }
+
+    fn no_tint(&mut self) {
+        self.style.set_tint(None);
+    }
+
+    // Images aren't recorded, so there's nothing for these to change.
+    fn smooth(&mut self) {}
+
+    fn no_smooth(&mut self) {}
+
+    fn get_data(&self) -> &[u32] {
+        &self.blank
+    }
+
+    fn load_pixels(&mut self) {
+        self.warn_once(
+            "load_pixels",
+            "Vector backends don't have pixels to load, so the pixels are all transparent.",
+        );
+        let canvas = self.to_image();
+        self.pixels.load(canvas);
+    }
+
+    fn pixels_mut(&mut self) -> &mut [u8] {
+        self.pixels.data_mut()
+    }
+
+    fn update_pixels(&mut self) {
+        self.warn_once(
+            "update_pixels",
+            "Pixels can't be recorded by vector backends, so the canvas isn't changed.",
+        );
+    }
+
+    fn get(&self, _x: f32, _y: f32) -> Color {
+        Color::new(0, 0, 0, 0)
+    }
+
+    fn get_region(&self, x: f32, y: f32, w: f32, h: f32) -> P5Image {
+        region(&self.to_image(), x, y, w, h)
+    }
+
+    fn set<C: IntoColor>(&mut self, x: f32, y: f32, color: C) {
+        let color = color.into_color(self.style.color_mode);
+        if !self.pixels.is_loaded() {
+            self.load_pixels();
+        }
+        self.pixels.set(x, y, color);
+    }
+
+    /// Saves everything that has been drawn to `path` in the vector format `F`, regardless of the
+    /// extension of `path`.
+    fn save_canvas<P: AsRef<std::path::Path>>(&self, path: P) -> image::ImageResult<()> {
+        self.format.save(&self.canvas, path.as_ref())?;
+        Ok(())
+    }
+}
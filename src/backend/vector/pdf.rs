@@ -0,0 +1,595 @@
+use crate::backend::path::{Path, Segment};
+use crate::backend::vector::{Canvas, Element, Stroke, VectorFormat, VectorP5};
+use crate::p5::{StrokeCap, StrokeJoin};
+use crate::Color;
+use font_kit::font::Font;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// A backend that records everything that's drawn, and saves it as a PDF document with
+/// [`save_canvas`](crate::P5Trait::save_canvas). When the sketch is rendered without a window, by
+/// [`run_headless`](crate::Sketch::run_headless) for example, each frame of `draw` becomes a
+/// separate page, and more pages can be added with [`add_page`](crate::PdfP5::add_page).
+pub type PdfP5 = VectorP5<Pdf>;
+
+/// The [PDF](https://en.wikipedia.org/wiki/PDF) format, used by [`PdfP5`](crate::PdfP5). Fonts
+/// used by `text` are embedded in the document, so it looks the same on computers that don't have
+/// them installed.
+#[derive(Default)]
+pub struct Pdf {
+    /// The elements of each canvas that pages have been taken from, where a new canvas starts
+    /// whenever `background` clears it. Until then, elements are only added to the canvas, so the
+    /// pages taken from it share these elements, and each page only copies the ones drawn since
+    /// the page before it.
+    canvases: Vec<Vec<Element>>,
+    /// How many times the last of the `canvases` had been cleared by `background`.
+    clears: usize,
+    /// Every page that has been finished so far. The canvas that is currently being drawn on
+    /// becomes the last page when the document is saved, unless it's already the last page.
+    pages: Vec<Page>,
+    /// Whether `add_page` has been called since the last frame ended, in which case the end of the
+    /// frame doesn't add the same page again.
+    added: bool,
+}
+
+/// A finished page, which shows the first `len` elements of one of the canvases kept by [`Pdf`].
+struct Page {
+    canvas: usize,
+    len: usize,
+}
+
+impl Pdf {
+    /// Finishes a page showing everything that's on the `canvas`.
+    fn add_page(&mut self, canvas: &Canvas) {
+        match self.canvases.last_mut() {
+            Some(elements) if self.clears == canvas.clears => {
+                elements.extend_from_slice(&canvas.elements[elements.len()..]);
+            }
+            _ => {
+                self.canvases.push(canvas.elements.clone());
+                self.clears = canvas.clears;
+            }
+        }
+        self.pages.push(Page {
+            canvas: self.canvases.len() - 1,
+            len: canvas.elements.len(),
+        });
+    }
+
+    /// Whether the last page shows exactly what's on the `canvas`, since nothing has been drawn
+    /// after it was added.
+    fn is_last_page(&self, canvas: &Canvas) -> bool {
+        self.pages.last().is_some_and(|page| {
+            page.canvas + 1 == self.canvases.len()
+                && self.clears == canvas.clears
+                && page.len == canvas.elements.len()
+        })
+    }
+}
+
+impl VectorFormat for Pdf {
+    fn save(&self, canvas: &Canvas, path: &std::path::Path) -> std::io::Result<()> {
+        let mut pages: Vec<&[Element]> = self
+            .pages
+            .iter()
+            .map(|page| &self.canvases[page.canvas][..page.len])
+            .collect();
+        if !self.is_last_page(canvas) {
+            pages.push(&canvas.elements);
+        }
+        std::fs::write(path, to_pdf(&pages, canvas.width, canvas.height))
+    }
+
+    fn end_frame(&mut self, canvas: &Canvas) {
+        let added = std::mem::take(&mut self.added);
+        if !(added && self.is_last_page(canvas)) {
+            self.add_page(canvas);
+        }
+    }
+}
+
+impl VectorP5<Pdf> {
+    /// Finishes the current page, so that everything that has been drawn so far is saved as a
+    /// separate page. Drawing continues on top of the same canvas, so call `background` to start
+    /// with a blank page. When the sketch is rendered without a window, pages are added at the end
+    /// of every frame of `draw` automatically, unless nothing has been drawn since calling this,
+    /// so this is only needed for documents drawn entirely in `setup`, for more than one page per
+    /// frame, or for choosing which frames become pages when the sketch is run in a window.
+    pub fn add_page(&mut self) {
+        self.format.add_page(&self.canvas);
+        self.format.added = true;
+    }
+}
+
+/// A PDF font resource, embedding a font used on one of the pages.
+struct FontResource {
+    /// The name used to refer to the font in content streams, like `F0`.
+    name: String,
+    font: Font,
+}
+
+/// The resources shared by every page of the document, collected while writing the pages.
+#[derive(Default)]
+struct Resources {
+    /// Fonts, keyed by their PostScript name.
+    fonts: HashMap<String, FontResource>,
+    /// The fill and stroke opacities of each graphics state. The index of each one is used as its
+    /// name, like `GS0`.
+    opacities: Vec<(u8, u8)>,
+}
+
+impl Resources {
+    /// Returns the name of the font resource for `font`, adding it if it hasn't been used yet.
+    fn font(&mut self, font: &Font) -> String {
+        let key = font.postscript_name().unwrap_or_else(|| font.family_name());
+        let name = format!("F{}", self.fonts.len());
+        self.fonts
+            .entry(key)
+            .or_insert_with(|| FontResource {
+                name,
+                font: font.clone(),
+            })
+            .name
+            .clone()
+    }
+
+    /// Returns the name of the graphics state with the given fill and stroke opacities, adding it
+    /// if it hasn't been used yet.
+    fn opacity(&mut self, fill: u8, stroke: u8) -> String {
+        let index = match self.opacities.iter().position(|&o| o == (fill, stroke)) {
+            Some(index) => index,
+            None => {
+                self.opacities.push((fill, stroke));
+                self.opacities.len() - 1
+            }
+        };
+        format!("GS{}", index)
+    }
+}
+
+/// Writes the objects of a PDF file, keeping track of where each one starts for the
+/// cross-reference table.
+struct Writer {
+    buf: Vec<u8>,
+    /// The byte offset of each object, indexed by its number minus one.
+    offsets: Vec<usize>,
+}
+
+impl Writer {
+    /// Starts a document for the given PDF `version`, like `1.4`, with room for `objects` objects.
+    fn new(version: &str, objects: usize) -> Writer {
+        let mut buf = format!("%PDF-{}\n", version).into_bytes();
+        // The comment with bytes above 127 marks the file as binary, as recommended by the spec.
+        buf.extend_from_slice(b"%\xe2\xe3\xcf\xd3\n");
+        Writer {
+            buf,
+            offsets: vec![0; objects],
+        }
+    }
+
+    fn object(&mut self, id: usize, body: &str) {
+        self.offsets[id - 1] = self.buf.len();
+        self.buf
+            .extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", id, body).as_bytes());
+    }
+
+    /// Writes a stream object, where `dict` contains any entries other than its `/Length`.
+    fn stream(&mut self, id: usize, dict: &str, data: &[u8]) {
+        self.offsets[id - 1] = self.buf.len();
+        self.buf.extend_from_slice(
+            format!(
+                "{} 0 obj\n<< {} /Length {} >>\nstream\n",
+                id,
+                dict,
+                data.len()
+            )
+            .as_bytes(),
+        );
+        self.buf.extend_from_slice(data);
+        self.buf.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    /// Writes the cross-reference table and trailer, with `root` as the document catalog.
+    fn finish(mut self, root: usize) -> Vec<u8> {
+        let xref = self.buf.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", self.offsets.len() + 1);
+        for offset in &self.offsets {
+            let _ = writeln!(table, "{:010} 00000 n ", offset);
+        }
+        let _ = write!(
+            table,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.offsets.len() + 1,
+            root,
+            xref
+        );
+        self.buf.extend_from_slice(table.as_bytes());
+        self.buf
+    }
+}
+
+/// Serializes `pages`, each of which is `width` by `height`, into a PDF document.
+fn to_pdf(pages: &[&[Element]], width: usize, height: usize) -> Vec<u8> {
+    let mut resources = Resources::default();
+    let contents: Vec<String> = pages
+        .iter()
+        .map(|elements| content_stream(elements, height, &mut resources))
+        .collect();
+
+    // Objects 1 to 3 are the catalog, the page tree and the resources, followed by three objects
+    // for each font (the font, its descriptor and its data), and then two for each page (the page
+    // and its content stream).
+    let mut fonts: Vec<&FontResource> = resources.fonts.values().collect();
+    fonts.sort_by(|a, b| a.name.cmp(&b.name));
+    let first_page = 4 + 3 * fonts.len();
+    let font_files: Vec<Option<FontFile>> =
+        fonts.iter().map(|font| FontFile::new(&font.font)).collect();
+    // Embedded OpenType fonts were added in PDF 1.6, everything else only needs PDF 1.4.
+    let version = if font_files
+        .iter()
+        .any(|file| matches!(file, Some(FontFile::OpenType(_))))
+    {
+        "1.6"
+    } else {
+        "1.4"
+    };
+    let mut writer = Writer::new(version, first_page - 1 + 2 * pages.len());
+
+    writer.object(1, "<< /Type /Catalog /Pages 2 0 R >>");
+
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", first_page + 2 * i))
+        .collect();
+    writer.object(
+        2,
+        &format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        ),
+    );
+
+    let mut dict = String::from("<< /Font <<");
+    for (i, font) in fonts.iter().enumerate() {
+        let _ = write!(dict, " /{} {} 0 R", font.name, 4 + 3 * i);
+    }
+    dict.push_str(" >> /ExtGState <<");
+    for (i, (fill, stroke)) in resources.opacities.iter().enumerate() {
+        let _ = write!(
+            dict,
+            " /GS{} << /ca {} /CA {} >>",
+            i,
+            f32::from(*fill) / 255.,
+            f32::from(*stroke) / 255.
+        );
+    }
+    dict.push_str(" >> >>");
+    writer.object(3, &dict);
+
+    for (i, (font, file)) in fonts.iter().zip(&font_files).enumerate() {
+        write_font(&mut writer, 4 + 3 * i, &font.font, file.as_ref());
+    }
+
+    for (i, content) in contents.iter().enumerate() {
+        let id = first_page + 2 * i;
+        writer.object(
+            id,
+            &format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources 3 0 R /Contents {} 0 R >>",
+                width,
+                height,
+                id + 1
+            ),
+        );
+        writer.stream(id + 1, "", content.as_bytes());
+    }
+
+    writer.finish(1)
+}
+
+/// Writes the drawing operators for the `elements` on a page that's `height` tall, adding any fonts
+/// and opacities that are used to `resources`.
+fn content_stream(elements: &[Element], height: usize, resources: &mut Resources) -> String {
+    let mut s = String::new();
+
+    // PDF puts the origin at the bottom left with the y-axis pointing up, so flip the page to
+    // match the canvas.
+    let _ = writeln!(s, "1 0 0 -1 0 {} cm", height);
+
+    for element in elements {
+        s.push_str("q\n");
+        match element {
+            Element::Path { path, fill, stroke } => {
+                let fill_alpha = fill.map_or(255, |fill| fill.a);
+                let stroke_alpha = stroke.as_ref().map_or(255, |stroke| stroke.color.a);
+                if fill_alpha != 255 || stroke_alpha != 255 {
+                    let _ = writeln!(s, "/{} gs", resources.opacity(fill_alpha, stroke_alpha));
+                }
+                if let Some(fill) = fill {
+                    write_color(&mut s, *fill, "rg");
+                }
+                if let Some(stroke) = stroke {
+                    write_color(&mut s, stroke.color, "RG");
+                    write_stroke(&mut s, stroke);
+                }
+
+                write_path(&mut s, path);
+                s.push_str(match (fill, stroke) {
+                    (Some(_), Some(_)) => "B\n",
+                    (Some(_), None) => "f\n",
+                    _ => "S\n",
+                });
+            }
+            Element::Text {
+                text,
+                font,
+                size,
+                x,
+                y,
+                transform: t,
+                fill,
+            } => {
+                if fill.a != 255 {
+                    let _ = writeln!(s, "/{} gs", resources.opacity(fill.a, 255));
+                }
+                write_color(&mut s, *fill, "rg");
+                let _ = writeln!(
+                    s,
+                    "{} {} {} {} {} {} cm",
+                    t.m11, t.m12, t.m21, t.m22, t.m31, t.m32
+                );
+                // The text matrix flips the glyphs back upright, since the page itself is flipped.
+                let _ = writeln!(
+                    s,
+                    "BT /{} {} Tf 1 0 0 -1 {} {} Tm ({}) Tj ET",
+                    resources.font(font),
+                    size,
+                    x,
+                    y,
+                    encode_text(text)
+                );
+            }
+        }
+        s.push_str("Q\n");
+    }
+
+    s
+}
+
+/// Writes `color` with the operator `op`, which is `rg` for fills and `RG` for strokes.
+fn write_color(s: &mut String, color: Color, op: &str) {
+    let _ = writeln!(
+        s,
+        "{} {} {} {}",
+        f32::from(color.r) / 255.,
+        f32::from(color.g) / 255.,
+        f32::from(color.b) / 255.,
+        op
+    );
+}
+
+/// Writes the graphics state operators for the weight, cap, join and dash pattern of `stroke`.
+fn write_stroke(s: &mut String, stroke: &Stroke) {
+    let cap = match stroke.cap {
+        StrokeCap::Square => 0,
+        StrokeCap::Round => 1,
+        StrokeCap::Project => 2,
+    };
+    let join = match stroke.join {
+        StrokeJoin::Miter => 0,
+        StrokeJoin::Round => 1,
+        StrokeJoin::Bevel => 2,
+    };
+    let _ = writeln!(
+        s,
+        "{} w {} J {} j {} M",
+        stroke.weight, cap, join, stroke.miter_limit
+    );
+
+    if !stroke.dash.is_empty() {
+        let dash: Vec<String> = stroke.dash.iter().map(f32::to_string).collect();
+        let _ = writeln!(s, "[{}] {} d", dash.join(" "), stroke.dash_offset);
+    }
+}
+
+/// Writes the path construction operators for `path`. PDF only has cubic curves, so quadratic
+/// curves are raised to cubic ones.
+fn write_path(s: &mut String, path: &Path) {
+    let mut start = euclid::point2(0., 0.);
+    let mut current = start;
+    for segment in &path.segments {
+        let _ = match *segment {
+            Segment::MoveTo(p) => {
+                start = p;
+                current = p;
+                writeln!(s, "{} {} m", p.x, p.y)
+            }
+            Segment::LineTo(p) => {
+                current = p;
+                writeln!(s, "{} {} l", p.x, p.y)
+            }
+            Segment::QuadTo(c, p) => {
+                let c1 = current.lerp(c, 2. / 3.);
+                let c2 = p.lerp(c, 2. / 3.);
+                current = p;
+                writeln!(s, "{} {} {} {} {} {} c", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+            }
+            Segment::CubicTo(c1, c2, p) => {
+                current = p;
+                writeln!(s, "{} {} {} {} {} {} c", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+            }
+            Segment::Close => {
+                current = start;
+                writeln!(s, "h")
+            }
+        };
+    }
+}
+
+/// Encodes `text` as the contents of a PDF string, using the Latin-1 subset of WinAnsiEncoding.
+/// Characters outside of it are replaced with `?`.
+fn encode_text(text: &str) -> String {
+    let mut s = String::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                s.push('\\');
+                s.push(c);
+            }
+            ' '..='~' => s.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(s, "\\{:03o}", c as u32);
+            }
+            _ => s.push('?'),
+        }
+    }
+    s
+}
+
+/// Writes `font` as a font embedded in the document, using the objects `id` (the font), `id + 1`
+/// (its descriptor) and `id + 2` (its data, if it has any).
+fn write_font(writer: &mut Writer, id: usize, font: &Font, file: Option<&FontFile>) {
+    let metrics = font.metrics();
+    // PDF measures glyphs in thousandths of an em.
+    let scale = 1000. / metrics.units_per_em as f32;
+    let name = font
+        .postscript_name()
+        .unwrap_or_else(|| font.family_name())
+        .replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "");
+
+    // The widths of each character from `FIRST_CHAR` to `LAST_CHAR`, matching `encode_text`.
+    const FIRST_CHAR: u32 = 32;
+    const LAST_CHAR: u32 = 255;
+    let widths: Vec<String> = (FIRST_CHAR..=LAST_CHAR)
+        .map(|code| {
+            std::char::from_u32(code)
+                .filter(|c| !('\u{7f}'..'\u{a0}').contains(c))
+                .and_then(|c| font.glyph_for_char(c))
+                .and_then(|glyph| font.advance(glyph).ok())
+                .map_or(0, |advance| (advance.x() * scale).round() as i32)
+                .to_string()
+        })
+        .collect();
+
+    // Fonts with CFF outlines are Type 1 fonts as far as PDF is concerned.
+    let subtype = match file {
+        Some(FontFile::OpenType(_)) => "Type1",
+        _ => "TrueType",
+    };
+    writer.object(
+        id,
+        &format!(
+            "<< /Type /Font /Subtype /{} /BaseFont /{} /FirstChar {} /LastChar {} /Widths [{}] /Encoding /WinAnsiEncoding /FontDescriptor {} 0 R >>",
+            subtype,
+            name,
+            FIRST_CHAR,
+            LAST_CHAR,
+            widths.join(" "),
+            id + 1
+        ),
+    );
+
+    let font_file = match file {
+        Some(FontFile::TrueType(_)) => format!(" /FontFile2 {} 0 R", id + 2),
+        Some(FontFile::OpenType(_)) => format!(" /FontFile3 {} 0 R", id + 2),
+        None => String::new(),
+    };
+    let bbox = metrics.bounding_box;
+    writer.object(
+        id + 1,
+        &format!(
+            "<< /Type /FontDescriptor /FontName /{} /Flags 32 /FontBBox [{} {} {} {}] /ItalicAngle 0 /Ascent {} /Descent {} /CapHeight {} /StemV 80{} >>",
+            name,
+            (bbox.min_x() * scale).round(),
+            (bbox.min_y() * scale).round(),
+            (bbox.max_x() * scale).round(),
+            (bbox.max_y() * scale).round(),
+            (metrics.ascent * scale).round(),
+            (metrics.descent * scale).round(),
+            (metrics.cap_height * scale).round(),
+            font_file
+        ),
+    );
+
+    // TODO: Subset the font, instead of embedding all of it.
+    match file {
+        Some(FontFile::TrueType(data)) => {
+            writer.stream(id + 2, &format!("/Length1 {}", data.len()), data)
+        }
+        Some(FontFile::OpenType(data)) => writer.stream(id + 2, "/Subtype /OpenType", data),
+        None => {
+            eprintln!("Warning -- `P5::save_canvas` -- The font `{}` isn't a TrueType or OpenType font, so it can't be embedded in the PDF, and viewers will show a similar font instead.", name);
+            writer.object(id + 2, "null");
+        }
+    }
+}
+
+/// The data of a font, in one of the formats that can be embedded in a PDF.
+enum FontFile {
+    /// A TrueType font, embedded as a `/FontFile2`.
+    TrueType(Vec<u8>),
+    /// An OpenType font with CFF outlines, embedded as a `/FontFile3` with the `/OpenType`
+    /// subtype.
+    OpenType(Vec<u8>),
+}
+
+/// The tables that are copied into embedded fonts, sorted by their tags. These are all that PDF
+/// viewers need to draw the glyphs of a TrueType font, or of an OpenType font with CFF outlines.
+const FONT_TABLES: [&[u8; 4]; 14] = [
+    b"CFF ", b"OS/2", b"cmap", b"cvt ", b"fpgm", b"glyf", b"head", b"hhea", b"hmtx", b"loca",
+    b"maxp", b"name", b"post", b"prep",
+];
+
+impl FontFile {
+    /// Builds a font file for `font` from the tables that font-kit loads for it, which also works
+    /// for fonts that are part of a collection. Returns `None` if the font has neither TrueType
+    /// nor CFF outlines.
+    fn new(font: &Font) -> Option<FontFile> {
+        let tables: Vec<(&[u8; 4], Box<[u8]>)> = FONT_TABLES
+            .iter()
+            .filter_map(|&tag| Some((tag, font.load_font_table(u32::from_be_bytes(*tag))?)))
+            .collect();
+        let has_table = |tag: &[u8; 4]| tables.iter().any(|(t, _)| *t == tag);
+        if has_table(b"glyf") {
+            Some(FontFile::TrueType(font_data(&[0, 1, 0, 0], &tables)))
+        } else if has_table(b"CFF ") {
+            Some(FontFile::OpenType(font_data(b"OTTO", &tables)))
+        } else {
+            None
+        }
+    }
+}
+
+/// Writes a font file with the given `version` that holds `tables`, which have to be sorted by
+/// their tags. The checksum of the whole font in its `head` table is left as it was, so it's stale,
+/// but PDF viewers don't check it.
+fn font_data(version: &[u8; 4], tables: &[(&[u8; 4], Box<[u8]>)]) -> Vec<u8> {
+    let count = tables.len() as u16;
+    // The directory records how to binary search it, using the largest power of two tables.
+    let entry_selector = 15 - count.leading_zeros() as u16;
+    let search_range = 16 << entry_selector;
+    let range_shift = 16 * count - search_range;
+    let mut font = version.to_vec();
+    for value in &[count, search_range, entry_selector, range_shift] {
+        font.extend_from_slice(&value.to_be_bytes());
+    }
+
+    let mut contents = Vec::new();
+    for (tag, table) in tables {
+        let start = contents.len();
+        contents.extend_from_slice(table);
+        // Each table starts on a four byte boundary, and is checksummed along with its padding.
+        contents.resize((contents.len() + 3) & !3, 0);
+        let checksum = contents[start..].chunks_exact(4).fold(0u32, |sum, word| {
+            sum.wrapping_add(u32::from_be_bytes([word[0], word[1], word[2], word[3]]))
+        });
+
+        font.extend_from_slice(*tag);
+        font.extend_from_slice(&checksum.to_be_bytes());
+        let offset = 12 + 16 * tables.len() + start;
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+    }
+    font.extend(contents);
+    font
+}
@@ -0,0 +1,148 @@
+use crate::backend::path::{Path, Segment};
+use crate::backend::vector::{Canvas, Element, Stroke, VectorFormat, VectorP5};
+use crate::p5::{StrokeCap, StrokeJoin};
+use crate::Color;
+use std::fmt::Write;
+
+/// A backend that records everything that's drawn, and saves it as an SVG file with
+/// [`save_canvas`](crate::P5Trait::save_canvas).
+pub type SvgP5 = VectorP5<Svg>;
+
+/// The [SVG](https://www.w3.org/TR/SVG11/) format, used by [`SvgP5`](crate::SvgP5).
+#[derive(Debug, Default)]
+pub struct Svg;
+
+impl VectorFormat for Svg {
+    fn save(&self, canvas: &Canvas, path: &std::path::Path) -> std::io::Result<()> {
+        std::fs::write(path, to_svg(canvas))
+    }
+}
+
+/// Serializes the `canvas` into an SVG document.
+fn to_svg(canvas: &Canvas) -> String {
+    let mut svg = String::new();
+
+    // Writing to a `String` never fails, so the results are ignored throughout.
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = canvas.width,
+        h = canvas.height
+    );
+
+    for element in &canvas.elements {
+        match element {
+            Element::Path { path, fill, stroke } => {
+                let _ = write!(svg, r#"<path d="{}""#, path_data(path));
+                match fill {
+                    Some(fill) => write_paint(&mut svg, "fill", *fill),
+                    None => svg.push_str(r#" fill="none""#),
+                }
+                if let Some(stroke) = stroke {
+                    write_stroke(&mut svg, stroke);
+                }
+                svg.push_str("/>\n");
+            }
+            Element::Text {
+                text,
+                font,
+                size,
+                x,
+                y,
+                transform: t,
+                fill,
+            } => {
+                let _ = write!(
+                    svg,
+                    r#"<text x="{}" y="{}" font-family="{}" font-size="{}" transform="matrix({} {} {} {} {} {})""#,
+                    x,
+                    y,
+                    escape(&font.family_name()),
+                    size,
+                    t.m11,
+                    t.m12,
+                    t.m21,
+                    t.m22,
+                    t.m31,
+                    t.m32
+                );
+                write_paint(&mut svg, "fill", *fill);
+                let _ = writeln!(svg, ">{}</text>", escape(text));
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Converts `path` into the syntax used by the `d` attribute of a `<path>`.
+fn path_data(path: &Path) -> String {
+    let mut d = String::new();
+    for segment in &path.segments {
+        let _ = match segment {
+            Segment::MoveTo(p) => write!(d, "M{} {}", p.x, p.y),
+            Segment::LineTo(p) => write!(d, "L{} {}", p.x, p.y),
+            Segment::QuadTo(c, p) => write!(d, "Q{} {} {} {}", c.x, c.y, p.x, p.y),
+            Segment::CubicTo(c1, c2, p) => {
+                write!(d, "C{} {} {} {} {} {}", c1.x, c1.y, c2.x, c2.y, p.x, p.y)
+            }
+            Segment::Close => write!(d, "Z"),
+        };
+    }
+    d
+}
+
+/// Writes a `fill` or `stroke` attribute for `color`, along with its opacity if it's not opaque.
+fn write_paint(svg: &mut String, attribute: &str, color: Color) {
+    let _ = write!(
+        svg,
+        r##" {}="#{:02x}{:02x}{:02x}""##,
+        attribute, color.r, color.g, color.b
+    );
+    if color.a != 255 {
+        let _ = write!(
+            svg,
+            r#" {}-opacity="{}""#,
+            attribute,
+            f32::from(color.a) / 255.
+        );
+    }
+}
+
+/// Writes the attributes for `stroke`. The cap, join and miter limit are only written if they're
+/// different from the defaults in SVG.
+fn write_stroke(svg: &mut String, stroke: &Stroke) {
+    write_paint(svg, "stroke", stroke.color);
+    let _ = write!(svg, r#" stroke-width="{}""#, stroke.weight);
+
+    match stroke.cap {
+        StrokeCap::Round => svg.push_str(r#" stroke-linecap="round""#),
+        StrokeCap::Square => {}
+        StrokeCap::Project => svg.push_str(r#" stroke-linecap="square""#),
+    }
+    match stroke.join {
+        StrokeJoin::Miter if stroke.miter_limit != 4. => {
+            let _ = write!(svg, r#" stroke-miterlimit="{}""#, stroke.miter_limit);
+        }
+        StrokeJoin::Miter => {}
+        StrokeJoin::Bevel => svg.push_str(r#" stroke-linejoin="bevel""#),
+        StrokeJoin::Round => svg.push_str(r#" stroke-linejoin="round""#),
+    }
+
+    if !stroke.dash.is_empty() {
+        let dash: Vec<String> = stroke.dash.iter().map(f32::to_string).collect();
+        let _ = write!(svg, r#" stroke-dasharray="{}""#, dash.join(" "));
+        if stroke.dash_offset != 0. {
+            let _ = write!(svg, r#" stroke-dashoffset="{}""#, stroke.dash_offset);
+        }
+    }
+}
+
+/// Escapes the characters that have a special meaning in XML.
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
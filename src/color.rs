@@ -1,4 +1,5 @@
 /// Stores a color as a premultiplied RGBA value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
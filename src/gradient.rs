@@ -0,0 +1,228 @@
+use crate::p5::AngleMode;
+use crate::{Color, ColorMode, IntoColor, RGB};
+use euclid::default::{Box2D, Transform2D};
+use euclid::point2;
+use std::f32::consts::TAU;
+
+/// The shape of a [`Gradient`](crate::Gradient), which determines where each of its colors is.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum GradientKind {
+    /// Changes color along the line from `(x1, y1)` to `(x2, y2)`.
+    Linear { x1: f32, y1: f32, x2: f32, y2: f32 },
+    /// Changes color from the center `(x, y)` out to the circle with the radius `r`.
+    Radial { x: f32, y: f32, r: f32 },
+    /// Changes color clockwise around the center `(x, y)`, starting from `angle`.
+    Conic { x: f32, y: f32, angle: f32 },
+}
+
+/// A gradient that shapes can be filled or outlined with, using
+/// [`fill_gradient`](crate::P5Trait::fill_gradient) and
+/// [`stroke_gradient`](crate::P5Trait::stroke_gradient). It's made up of color stops, which each
+/// give the color at an offset from 0 (the start of the gradient) to 1 (the end of the gradient).
+/// Between the stops, the colors blend smoothly, and beyond the first and last stop, the colors of
+/// those stops are used. A linear gradient whose line has no length, or a radial gradient whose
+/// circle has no size, has nowhere to blend, so it's the color of its first stop everywhere.
+///
+/// Like the coordinates of shapes, the coordinates of a gradient are transformed by the current
+/// transformation when it's used to draw a shape.
+///
+/// ```no_run
+/// # use p5_rs::*;
+/// # let mut p5 = <P5 as Backend>::new(400, 400);
+/// let gradient = Gradient::linear(0., 0., 400., 0.)
+///     .color_stop(0., (255., 0., 0.))
+///     .color_stop(1., (0., 0., 255.));
+/// p5.fill_gradient(&gradient);
+/// p5.rect(0., 0., 400., 400., None);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub(crate) kind: GradientKind,
+    /// The color stops, sorted by their offsets.
+    pub(crate) stops: Vec<(f32, Color)>,
+    /// The color mode used to interpret the colors of new stops.
+    color_mode: ColorMode,
+}
+
+impl Gradient {
+    fn new(kind: GradientKind) -> Gradient {
+        Gradient {
+            kind,
+            stops: Vec::new(),
+            color_mode: RGB,
+        }
+    }
+
+    /// Creates a gradient which starts at `(x1, y1)` and ends at `(x2, y2)`, changing color along
+    /// the line between them.
+    pub fn linear(x1: f32, y1: f32, x2: f32, y2: f32) -> Gradient {
+        Gradient::new(GradientKind::Linear { x1, y1, x2, y2 })
+    }
+
+    /// Creates a gradient which starts at `(x, y)`, and ends at the circle around it with the
+    /// radius `r`.
+    pub fn radial(x: f32, y: f32, r: f32) -> Gradient {
+        Gradient::new(GradientKind::Radial { x, y, r })
+    }
+
+    /// Creates a gradient which goes once clockwise around `(x, y)`, starting and ending at
+    /// `angle`, which is measured from the positive x-axis. Like the angles of rotations, the
+    /// angle is in the [`angle_mode`](crate::P5Trait::angle_mode) that's current when the gradient
+    /// is passed to `fill_gradient` or `stroke_gradient`.
+    pub fn conic(x: f32, y: f32, angle: f32) -> Gradient {
+        Gradient::new(GradientKind::Conic { x, y, angle })
+    }
+
+    /// Changes how the colors of the stops added after this are interpreted, just like
+    /// [`color_mode`](crate::P5Trait::color_mode). The default is `RGB`.
+    pub fn color_mode(mut self, mode: ColorMode) -> Gradient {
+        self.color_mode = mode;
+        self
+    }
+
+    /// Adds a stop with the `color` at the `offset`, which is clamped between 0 and 1. If there's
+    /// already a stop at the same offset, the new stop is placed just after it, which makes the
+    /// gradient change color abruptly.
+    pub fn color_stop<C: IntoColor>(mut self, offset: f32, color: C) -> Gradient {
+        let offset = if offset.is_nan() {
+            0.
+        } else {
+            offset.clamp(0., 1.)
+        };
+        let i = self.stops.iter().take_while(|(o, _)| *o <= offset).count();
+        self.stops
+            .insert(i, (offset, color.into_color(self.color_mode)));
+        self
+    }
+
+    /// Returns a copy of the gradient with the angle of a conic gradient converted from `mode`
+    /// into radians, which is what the backends draw it with.
+    pub(crate) fn to_radians(&self, mode: AngleMode) -> Gradient {
+        let mut gradient = self.clone();
+        if let GradientKind::Conic { ref mut angle, .. } = gradient.kind {
+            *angle = mode.to_radians(*angle);
+        }
+        gradient
+    }
+
+    /// The color used by backends that can't draw gradients, which is the color of the first
+    /// stop. If there are no stops, the gradient is transparent.
+    pub(crate) fn fallback_color(&self) -> Color {
+        self.stops
+            .first()
+            .map_or(Color::new(0, 0, 0, 0), |&(_, color)| color)
+    }
+
+    /// Returns the single color that the whole gradient is, if it doesn't have any stops, or if
+    /// its line or circle has no size. That's the `fallback_color`, so a gradient like that looks
+    /// the same in every backend. Otherwise, returns `None`.
+    pub(crate) fn solid_color(&self) -> Option<Color> {
+        let degenerate = match self.kind {
+            GradientKind::Linear { x1, y1, x2, y2 } => x1 == x2 && y1 == y2,
+            GradientKind::Radial { r, .. } => r <= 0. || r.is_nan(),
+            GradientKind::Conic { .. } => false,
+        };
+        if self.stops.is_empty() || degenerate {
+            Some(self.fallback_color())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the color at the `offset` along the gradient.
+    pub(crate) fn color_at(&self, offset: f32) -> Color {
+        let after = self.stops.iter().position(|(o, _)| *o > offset);
+        let (start, end) = match after {
+            Some(0) => return self.fallback_color(),
+            Some(i) => (self.stops[i - 1], self.stops[i]),
+            None => match self.stops.last() {
+                Some(&(_, color)) => return color,
+                None => return Color::new(0, 0, 0, 0),
+            },
+        };
+
+        let t = (offset - start.0) / (end.0 - start.0);
+        let lerp = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+        let (a, b) = (start.1, end.1);
+        Color::new(
+            lerp(a.r, b.r),
+            lerp(a.g, b.g),
+            lerp(a.b, b.b),
+            lerp(a.a, b.a),
+        )
+    }
+
+    /// Returns the offset along the gradient of the point `(x, y)`, in the coordinates the
+    /// gradient was created with.
+    fn offset_at(&self, x: f32, y: f32) -> f32 {
+        match self.kind {
+            GradientKind::Linear { x1, y1, x2, y2 } => {
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                ((x - x1) * dx + (y - y1) * dy) / (dx * dx + dy * dy)
+            }
+            GradientKind::Radial { x: cx, y: cy, r } => (x - cx).hypot(y - cy) / r,
+            GradientKind::Conic {
+                x: cx,
+                y: cy,
+                angle,
+            } => ((y - cy).atan2(x - cx) - angle).rem_euclid(TAU) / TAU,
+        }
+    }
+
+    /// Draws the gradient, transformed by `transform`, into an image covering the pixels inside of
+    /// `bounds` on a canvas of the given `width` and `height`. Returns `None` if none of those
+    /// pixels are on the canvas. This is used by backends that can't draw a kind of gradient
+    /// themselves. It's much slower than a built-in gradient, so only the part of the canvas
+    /// that a shape is painted on should be drawn.
+    pub(crate) fn to_image(
+        &self,
+        transform: &Transform2D<f32>,
+        bounds: &Box2D<f32>,
+        width: i32,
+        height: i32,
+    ) -> Option<GradientImage> {
+        let (x0, y0) = (bounds.min.x.floor() as i32, bounds.min.y.floor() as i32);
+        let (x0, y0) = (x0.max(0), y0.max(0));
+        let (x1, y1) = (bounds.max.x.ceil() as i32, bounds.max.y.ceil() as i32);
+        let (x1, y1) = (x1.min(width), y1.min(height));
+        if x0 >= x1 || y0 >= y1 {
+            return None;
+        }
+
+        let size = ((x1 - x0) * (y1 - y0)) as usize;
+        let pixels = match transform.inverse() {
+            Some(inverse) => {
+                let mut pixels = Vec::with_capacity(size);
+                for y in y0..y1 {
+                    for x in x0..x1 {
+                        let p = inverse.transform_point(point2(x as f32 + 0.5, y as f32 + 0.5));
+                        let color = self.color_at(self.offset_at(p.x, p.y));
+                        let a = u16::from(color.a);
+                        let c = |c: u8| ((u16::from(c) * a + 127) / 255) as u8;
+                        pixels.push([c(color.r), c(color.g), c(color.b), color.a]);
+                    }
+                }
+                pixels
+            }
+            None => vec![[0; 4]; size],
+        };
+        Some(GradientImage {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+            pixels,
+        })
+    }
+}
+
+/// Part of a gradient, drawn into an image by [`Gradient::to_image`](Gradient::to_image).
+pub(crate) struct GradientImage {
+    /// The position of the image's top-left corner on the canvas.
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    /// The premultiplied RGBA components of each pixel, row by row.
+    pub(crate) pixels: Vec<[u8; 4]>,
+}
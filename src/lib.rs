@@ -1,12 +1,39 @@
 mod backend;
 mod color;
+mod gradient;
 mod p5;
+mod p5_image;
 mod sketch;
+mod snapshot;
 
+#[cfg(feature = "raqote")]
+pub use backend::raqote::RaqoteP5;
+#[cfg(feature = "tiny-skia")]
+pub use backend::tiny_skia::TinySkiaP5;
+pub use backend::vector::pdf::{Pdf, PdfP5};
+pub use backend::vector::svg::{Svg, SvgP5};
+pub use backend::vector::{Canvas, VectorFormat, VectorP5};
+pub use backend::Backend;
 pub use color::{Color, ColorMode, ColorModel, IntoColor, HSB, HSL, RGB};
+pub use gradient::Gradient;
 pub use minifb::Key;
 pub use p5::P5 as P5Trait;
-pub use p5::{RectMode, RectRounding};
+pub use p5::{
+    AngleMode, ArcMode, BlendMode, EllipseMode, EndMode, ImageMode, RectMode, RectRounding,
+    ShapeKind, StrokeCap, StrokeJoin,
+};
+pub use p5_image::P5Image;
 pub use sketch::Sketch;
+pub use snapshot::{assert_snapshot, UPDATE_SNAPSHOTS_VAR};
 
+/// The default backend, which draws sketches that don't specify a backend. This is chosen with
+/// cargo features: `raqote` (the default) or `tiny-skia`. If both are enabled, raqote is used.
+#[cfg(feature = "raqote")]
 pub type P5 = backend::raqote::RaqoteP5;
+#[cfg(all(feature = "tiny-skia", not(feature = "raqote")))]
+pub type P5 = backend::tiny_skia::TinySkiaP5;
+
+#[cfg(not(any(feature = "raqote", feature = "tiny-skia")))]
+compile_error!(
+    "p5-rs needs a backend to draw with, enable either the `raqote` or the `tiny-skia` feature."
+);
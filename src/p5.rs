@@ -1,4 +1,8 @@
-use crate::color::IntoColor;
+use crate::color::{Color, IntoColor};
+use crate::gradient::Gradient;
+use crate::p5_image::P5Image;
+use euclid::point2;
+use lyon_geom::CubicBezierSegment;
 
 pub trait P5 {
     fn background<C: IntoColor>(&mut self, c: C);
@@ -16,6 +20,23 @@ pub trait P5 {
     /// diameter of the circle.
     fn circle(&mut self, x: f32, y: f32, d: f32);
 
+    /// Modifies the location from which ellipses, circles and arcs are drawn by changing the way
+    /// in which their parameters are interpreted, as described in
+    /// [`EllipseMode`](crate::EllipseMode). The default mode is `EllipseMode::Center`.
+    fn ellipse_mode(&mut self, mode: EllipseMode);
+
+    /// Draws an arc to the screen. The arc is drawn along the outer edge of an ellipse defined by
+    /// the `x`, `y`, `w` and `h` parameters, which are interpreted the same way as for
+    /// ellipse(). The `start` and `stop` parameters specify the angles (in the current
+    /// angle_mode()) at which to draw the arc, clockwise from the positive x-axis. The arc is
+    /// always drawn clockwise from `start` to `stop`.
+    ///
+    /// The `mode` parameter determines how the arc is closed, as described in
+    /// [`ArcMode`](crate::ArcMode). If the arc is filled, the fill is always closed, even with
+    /// `ArcMode::Open`, which only leaves the stroke open.
+    #[allow(clippy::too_many_arguments)]
+    fn arc(&mut self, x: f32, y: f32, w: f32, h: f32, start: f32, stop: f32, mode: ArcMode);
+
     /// Draws a line (a direct path between two points) to the screen, with a default width of 1
     /// pixel. This width can be modified by using the stroke_weight() function. A line cannot be
     /// filled, therefore the fill() function will not affect the color of a line. So to color a
@@ -26,8 +47,21 @@ pub trait P5 {
     /// is the horizontal value for the point, the second param is the vertical value for the
     /// point. The color of the point is changed with the stroke() function. The size of the
     /// point can be changed with the stroke_weight() function.
+    ///
+    /// Like the stroke of other shapes, the position of the point is transformed by the current
+    /// transformation, but its size isn't. Points outside of the canvas are ignored.
     fn point(&mut self, x: f32, y: f32);
 
+    /// Draws a point at each of the `points`, exactly like calling `point` for each of them. This
+    /// is much faster than calling `point` separately when drawing lots of points, since they're
+    /// all drawn together as a single shape. This also means that where points with a
+    /// transparent stroke color overlap, they don't get any darker.
+    fn points(&mut self, points: &[(f32, f32)]) {
+        for &(x, y) in points {
+            self.point(x, y);
+        }
+    }
+
     /// Sets the width of the stroke used for lines, points and the border around shapes. All
     /// widths are set in units of pixels.
     fn stroke_weight(&mut self, weight: f32);
@@ -38,6 +72,35 @@ pub trait P5 {
 
     fn stroke<C: IntoColor>(&mut self, color: C);
 
+    /// Draws lines and the borders around shapes with a [`Gradient`](crate::Gradient) instead of a
+    /// single color, until `stroke` or `stroke_gradient` is called again. Points are still drawn
+    /// with a single color, which is the color of the gradient's first stop.
+    fn stroke_gradient(&mut self, gradient: &Gradient);
+
+    /// Sets the style for rendering line endings. These ends are either rounded, squared or
+    /// extended, as described in [`StrokeCap`](crate::StrokeCap). The default cap is
+    /// `StrokeCap::Square`, unlike in p5.js, where it's `StrokeCap::Round`.
+    fn stroke_cap(&mut self, cap: StrokeCap);
+
+    /// Sets the style of the joints which connect line segments. These joints are either mitered,
+    /// beveled or rounded, as described in [`StrokeJoin`](crate::StrokeJoin). The default joint
+    /// is `StrokeJoin::Miter`.
+    fn stroke_join(&mut self, join: StrokeJoin);
+
+    /// Sets the limit on the ratio of the length of a mitered joint to the stroke weight. Joints
+    /// that would be longer than this are beveled instead, so that sharp angles don't produce
+    /// extremely long spikes. The default limit is 10.
+    fn miter_limit(&mut self, limit: f32);
+
+    /// Sets the dash pattern used for strokes. The `pattern` alternates between the lengths of the
+    /// dashes and the gaps between them, in pixels. If it has an odd number of lengths, it's
+    /// repeated to make it even, so `&[5.]` gives dashes and gaps that are both 5 pixels long.
+    /// `offset` specifies how far into the pattern the stroke starts.
+    ///
+    /// An empty pattern gives a solid stroke again, which is the default. Patterns with negative
+    /// lengths, or where every length is zero, are ignored with a warning.
+    fn stroke_dash(&mut self, pattern: &[f32], offset: f32);
+
     /// Draws a quad on the canvas. A quad is a quadrilateral, a four sided polygon. It is similar
     /// to a rectangle, but the angles between its edges are not constrained to ninety degrees. The
     /// first pair of parameters (x1,y1) sets the first vertex and the subsequent pairs should
@@ -71,6 +134,105 @@ pub trait P5 {
     /// second point, and the last two arguments specify the third point.
     fn triangle(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32);
 
+    /// Using the begin_shape() and end_shape() functions allow creating more complex forms.
+    /// begin_shape() begins recording vertices for a shape and end_shape() stops recording. The
+    /// value of the `kind` parameter tells it which types of shapes to create from the provided
+    /// vertices, as described in [`ShapeKind`](crate::ShapeKind). Use `ShapeKind::Polygon` to
+    /// create an irregular polygon.
+    ///
+    /// After calling the begin_shape() function, a series of vertex() commands must follow. To
+    /// stop drawing the shape, call end_shape(). The whole shape is drawn with the fill, stroke
+    /// and transformation that are current when end_shape() is called.
+    fn begin_shape(&mut self, kind: ShapeKind);
+
+    /// All shapes are constructed by connecting a series of vertices. vertex() is used to specify
+    /// the vertex coordinates for points, lines, triangles, quads, and polygons. It is used
+    /// exclusively within the begin_shape() and end_shape() functions.
+    fn vertex(&mut self, x: f32, y: f32);
+
+    /// The end_shape() function is the companion to begin_shape() and may only be called after
+    /// begin_shape(). When end_shape() is called, all of the image data defined since the previous
+    /// call to begin_shape() is drawn. The `mode` parameter is used to specify whether the end of
+    /// the shape should be connected to its beginning, using `EndMode::Close`.
+    fn end_shape(&mut self, mode: EndMode);
+
+    /// Specifies vertex coordinates for Bézier curves. Each call to bezier_vertex() defines the
+    /// position of two control points and one anchor point of a Bézier curve, adding a new
+    /// segment to a line or shape. The first time bezier_vertex() is used within a begin_shape()
+    /// call, it must be prefaced with a call to vertex() to set the first anchor point. This
+    /// function must be used between begin_shape() and end_shape(), and only with
+    /// `ShapeKind::Polygon`.
+    fn bezier_vertex(&mut self, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32);
+
+    /// Specifies vertex coordinates for quadratic Bézier curves. Each call to quadratic_vertex()
+    /// defines the position of one control point and one anchor point of a Bézier curve, adding
+    /// a new segment to a line or shape. The first time quadratic_vertex() is used within a
+    /// begin_shape() call, it must be prefaced with a call to vertex() to set the first anchor
+    /// point. This function must be used between begin_shape() and end_shape(), and only with
+    /// `ShapeKind::Polygon`.
+    fn quadratic_vertex(&mut self, cx: f32, cy: f32, x3: f32, y3: f32);
+
+    /// Specifies vertex coordinates for curves. This function may only be used between
+    /// begin_shape() and end_shape(), and only with `ShapeKind::Polygon`.
+    ///
+    /// The first and last points in a series of curve_vertex() lines will be used to guide the
+    /// beginning and end of the curve. A minimum of four points is required to draw a tiny curve
+    /// between the second and third points. Adding a fifth point with curve_vertex() will draw
+    /// the curve between the second, third, and fourth points. The curve_vertex() function is an
+    /// implementation of Catmull-Rom splines.
+    fn curve_vertex(&mut self, x: f32, y: f32);
+
+    /// Draws a cubic Bézier curve on the screen. These curves are defined by a series of anchor
+    /// and control points. The first two parameters specify the first anchor point and the last
+    /// two parameters specify the other anchor point, which become the first and last points on
+    /// the curve. The middle parameters specify the two control points which define the shape of
+    /// the curve. Approximately speaking, control points "pull" the curve towards them.
+    #[allow(clippy::too_many_arguments)]
+    fn bezier(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32);
+
+    /// Draws a curved line on the screen between two points, given as the middle four
+    /// parameters. The first two parameters are a control point, as if the curve came from this
+    /// point even though it's not drawn. The last two parameters similarly describe the other
+    /// control point.
+    ///
+    /// Longer curves can be created by putting a series of curve() functions together or using
+    /// curve_vertex(). An additional function called curve_tightness() provides control for the
+    /// visual quality of the curve. The curve() function is an implementation of Catmull-Rom
+    /// splines.
+    #[allow(clippy::too_many_arguments)]
+    fn curve(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32, x4: f32, y4: f32);
+
+    /// Modifies the quality of forms created with curve() and curve_vertex(). The parameter
+    /// `amount` determines how the curve fits to the vertex points. The value 0.0 is the default
+    /// value for `amount` (this value defines the curves to be Catmull-Rom splines) and the value
+    /// 1.0 connects all the points with straight lines. Values within the range -5.0 and 5.0 will
+    /// deform the curves but will leave them recognizable and as values increase in magnitude,
+    /// they will continue to deform.
+    fn curve_tightness(&mut self, amount: f32);
+
+    /// Evaluates the Bézier at position `t` for points `a`, `b`, `c`, `d`. The parameters `a` and
+    /// `d` are the first and last points on the curve, and `b` and `c` are the control points.
+    /// The final parameter `t` varies between 0 and 1. This can be done once with the x
+    /// coordinates and a second time with the y coordinates to get the location of a Bézier
+    /// curve at `t`.
+    fn bezier_point(&self, a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+        bezier_1d(a, b, c, d).x(t)
+    }
+
+    /// Evaluates the tangent to the Bézier at position `t` for points `a`, `b`, `c`, and `d`. `a`
+    /// and `d` are the first and last points on the curve, and `b` and `c` are the control
+    /// points. The final parameter `t` varies between 0 and 1.
+    fn bezier_tangent(&self, a: f32, b: f32, c: f32, d: f32, t: f32) -> f32 {
+        bezier_1d(a, b, c, d).dx(t)
+    }
+
+    /// Evaluates the curve at position `t` for points `a`, `b`, `c`, `d`. The parameter `t`
+    /// varies between 0 and 1, `a` and `d` are control points of the curve, and `b` and `c` are
+    /// the start and end points of the curve. This can be done once with the x coordinates and a
+    /// second time with the y coordinates to get the location of a curve at `t`. The current
+    /// curve_tightness() is taken into account.
+    fn curve_point(&self, a: f32, b: f32, c: f32, d: f32, t: f32) -> f32;
+
     fn reset_matrix(&mut self);
 
     /// Multiplies the current matrix by the one specified through the parameters. This is a
@@ -86,6 +248,65 @@ pub trait P5 {
     /// rapidly fly off the screen.
     fn apply_matrix(&mut self, m11: f32, m12: f32, m21: f32, m22: f32, m31: f32, m32: f32);
 
+    /// The push() function saves the current drawing style settings and transformations, while
+    /// pop() restores these settings. Note that these functions are always used together. They
+    /// allow you to change the style and transformation settings and later return to what you
+    /// had. When a new state is started with push(), it builds on the current style and transform
+    /// information.
+    ///
+    /// push() stores information related to the current transformation state and style settings
+    /// controlled by the following functions: fill(), no_fill(), stroke(), no_stroke(),
+    /// stroke_weight(), stroke_cap(), stroke_join(), miter_limit(), stroke_dash(), rect_mode(),
+    /// ellipse_mode(), image_mode(), tint(), no_tint(), color_mode(), blend_mode(), erase(),
+    /// no_erase(), curve_tightness(), text_size(), text_font(), translate(), rotate(), scale(),
+    /// shear_x(), shear_y() and apply_matrix(). It also stores the clipping applied by end_clip().
+    fn push(&mut self);
+
+    /// Restores the drawing style settings and transformations saved by the most recent call to
+    /// [`push`](crate::P5Trait::push). If there was no matching call to `push`, a warning is
+    /// printed and nothing changes.
+    fn pop(&mut self);
+
+    /// Starts defining a clip. Instead of being drawn, the shapes and text drawn after this,
+    /// until [`end_clip`](crate::P5Trait::end_clip) is called, are combined into a region that
+    /// everything drawn afterwards is confined to. Lines and points don't cover any area, so they
+    /// don't add anything to the region. If `invert` is `true`, drawing is confined to the outside
+    /// of the region instead.
+    ///
+    /// ```no_run
+    /// # use p5_rs::*;
+    /// # let mut p5 = <P5 as Backend>::new(400, 400);
+    /// p5.push();
+    /// p5.begin_clip(false);
+    /// p5.circle(200., 200., 300.);
+    /// p5.end_clip();
+    /// // Only the part of the square inside of the circle is drawn.
+    /// p5.rect(100., 100., 300., 300., None);
+    /// p5.pop();
+    /// ```
+    fn begin_clip(&mut self, invert: bool);
+
+    /// Finishes defining the clip started by [`begin_clip`](crate::P5Trait::begin_clip), and
+    /// confines drawing to it. If drawing is already confined to a clip, it's confined to both of
+    /// them.
+    ///
+    /// Clipping can be undone by calling [`push`](crate::P5Trait::push) before `begin_clip`, and
+    /// [`pop`](crate::P5Trait::pop) once drawing shouldn't be clipped anymore. Like the
+    /// transformation, clipping is also removed at the start of each frame of `draw`.
+    fn end_clip(&mut self);
+
+    /// Defines a clip with the shapes drawn by `shapes`, the same as calling
+    /// [`begin_clip`](crate::P5Trait::begin_clip) before it and
+    /// [`end_clip`](crate::P5Trait::end_clip) after it.
+    fn clip<F: FnOnce(&mut Self)>(&mut self, shapes: F, invert: bool)
+    where
+        Self: Sized,
+    {
+        self.begin_clip(invert);
+        shapes(self);
+        self.end_clip();
+    }
+
     /// Specifies an amount to displace objects within the display window. The x parameter specifies
     /// left/right translation, the y parameter specifies up/down translation.
 
@@ -98,7 +319,8 @@ pub trait P5 {
         self.apply_matrix(1., 0., 0., 1., x, y);
     }
 
-    /// Rotates a shape by the amount specified by the angle parameter.
+    /// Rotates a shape by the amount specified by the angle parameter. Angles should be specified
+    /// in the current angle_mode(), which is radians by default.
     ///
     /// Objects are always rotated around their relative position to the origin and positive numbers
     /// rotate objects in a clockwise direction. Transformations apply to everything that happens
@@ -106,7 +328,7 @@ pub trait P5 {
     /// rotate(HALF_PI) and then rotate(HALF_PI) is the same as rotate(PI). All tranformations are
     /// reset when draw() begins again.
     fn rotate(&mut self, angle: f32) {
-        // TODO: Angle mode
+        let angle = self.get_angle_mode().to_radians(angle);
         let cos_a = angle.cos();
         let sin_a = angle.sin();
         self.apply_matrix(cos_a, sin_a, -sin_a, cos_a, 0., 0.);
@@ -135,7 +357,7 @@ pub trait P5 {
     /// shearX(PI/2) is the same as shearX(PI). If shearX() is called within the draw(), the
     /// transformation is reset when the loop begins again.
     fn shear_x(&mut self, angle: f32) {
-        let mut t = angle.tan();
+        let mut t = self.get_angle_mode().to_radians(angle).tan();
         if t.abs() > 1000. {
             t = 0.; // awful hack, but otherwise, raqote overflows when rendering.
         }
@@ -151,7 +373,7 @@ pub trait P5 {
     /// as shearY(PI). If shearY() is called within the draw(), the transformation is reset when
     /// the loop begins again.
     fn shear_y(&mut self, angle: f32) {
-        let mut t = angle.tan();
+        let mut t = self.get_angle_mode().to_radians(angle).tan();
         if t.abs() > 1_000. {
             t = 0.;
         }
@@ -167,15 +389,108 @@ pub trait P5 {
     /// TODO: allow for updating frame rate outside of `setup`.
     fn frame_rate(&mut self, fps: f32);
 
+    /// Returns the number of frames that have been displayed since the program started. Inside
+    /// setup() the value is 0, after the first iteration of draw it is 1, etc.
+    fn frame_count(&self) -> usize;
+
     /// colorMode() changes the way p5.js interprets color data. By default, the parameters for
     /// fill(), stroke(), background(), and color() are defined by values between 0 and 255
     /// using the RGB color model.
     fn color_mode(&mut self, mode: crate::ColorMode);
 
+    /// Sets the current mode of p5 to the given mode. The default mode is `AngleMode::Radians`.
+    /// The angle mode is used by every function that takes or returns an angle, like rotate(),
+    /// shear_x(), shear_y(), arc() and the trigonometry functions, like sin() and atan2().
+    fn angle_mode(&mut self, mode: AngleMode);
+
+    /// Returns the current angle mode, set by [`angle_mode`](crate::P5Trait::angle_mode).
+    fn get_angle_mode(&self) -> AngleMode;
+
+    /// Calculates the sine of an angle, which is interpreted according to the current
+    /// angle_mode().
+    fn sin(&self, angle: f32) -> f32 {
+        self.get_angle_mode().to_radians(angle).sin()
+    }
+
+    /// Calculates the cosine of an angle, which is interpreted according to the current
+    /// angle_mode().
+    fn cos(&self, angle: f32) -> f32 {
+        self.get_angle_mode().to_radians(angle).cos()
+    }
+
+    /// Calculates the tangent of an angle, which is interpreted according to the current
+    /// angle_mode().
+    fn tan(&self, angle: f32) -> f32 {
+        self.get_angle_mode().to_radians(angle).tan()
+    }
+
+    /// The inverse of sin(), returns the arc sine of a value. The result is in the range
+    /// -PI/2 to PI/2, or -90 to 90 degrees, depending on the current angle_mode().
+    fn asin(&self, value: f32) -> f32 {
+        self.get_angle_mode().from_radians(value.asin())
+    }
+
+    /// The inverse of cos(), returns the arc cosine of a value. The result is in the range 0 to
+    /// PI, or 0 to 180 degrees, depending on the current angle_mode().
+    fn acos(&self, value: f32) -> f32 {
+        self.get_angle_mode().from_radians(value.acos())
+    }
+
+    /// The inverse of tan(), returns the arc tangent of a value. The result is in the range
+    /// -PI/2 to PI/2, or -90 to 90 degrees, depending on the current angle_mode().
+    fn atan(&self, value: f32) -> f32 {
+        self.get_angle_mode().from_radians(value.atan())
+    }
+
+    /// Calculates the angle from a specified point to the coordinate origin as measured from the
+    /// positive x-axis. Note that the `y` coordinate comes first. The result is in the range -PI
+    /// to PI, or -180 to 180 degrees, depending on the current angle_mode().
+    fn atan2(&self, y: f32, x: f32) -> f32 {
+        self.get_angle_mode().from_radians(y.atan2(x))
+    }
+
+    /// Converts a radian measurement to its corresponding value in degrees, regardless of the
+    /// current angle_mode().
+    fn degrees(&self, radians: f32) -> f32 {
+        radians.to_degrees()
+    }
+
+    /// Converts a degree measurement to its corresponding value in radians, regardless of the
+    /// current angle_mode().
+    fn radians(&self, degrees: f32) -> f32 {
+        degrees.to_radians()
+    }
+
     fn fill<C: IntoColor>(&mut self, color: C);
 
+    /// Fills shapes and text with a [`Gradient`](crate::Gradient) instead of a single color, until
+    /// `fill`, `fill_gradient` or `no_fill` is called again.
+    fn fill_gradient(&mut self, gradient: &Gradient);
+
     fn no_fill(&mut self);
 
+    /// Sets how the shapes, text and points drawn after this are combined with what's already on
+    /// the canvas, as described in [`BlendMode`](crate::BlendMode). The default mode is
+    /// `BlendMode::Blend`. [`background`](crate::P5Trait::background) isn't affected, and always
+    /// replaces the whole canvas.
+    fn blend_mode(&mut self, mode: BlendMode);
+
+    /// All drawing that follows erase() removes from the canvas instead of adding to it, which
+    /// makes the erased areas transparent, for example when the canvas is saved with
+    /// [`save_canvas`](crate::P5Trait::save_canvas). `fill_strength` and `stroke_strength` are how
+    /// much the fill and stroke of shapes erase, from 0 to 255, regardless of the current
+    /// [`color_mode`](crate::P5Trait::color_mode). p5.js uses 255 for both by default.
+    ///
+    /// Only shapes that would be filled erase with their fill, and only shapes that would be
+    /// stroked erase with their stroke. The fill and stroke colors and the blend mode aren't
+    /// changed by erase(), and are used again after [`no_erase`](crate::P5Trait::no_erase).
+    /// [`background`](crate::P5Trait::background) isn't affected by erase().
+    fn erase(&mut self, fill_strength: f32, stroke_strength: f32);
+
+    /// Ends erasing started by [`erase`](crate::P5Trait::erase), so that shapes are drawn
+    /// normally again.
+    fn no_erase(&mut self);
+
     fn key_is_down(&self, key: crate::Key) -> bool;
 
     /// Draws text to the screen. Displays the information specified in the first parameter on the
@@ -207,7 +522,140 @@ pub trait P5 {
     /// ```
     fn text_font(&mut self, family_name: &str);
 
+    /// Loads an image from a file, which can then be drawn with
+    /// [`image`](crate::P5Trait::image). The format is chosen from the contents of the file, and
+    /// can be any of PNG, JPEG, BMP or GIF. Unlike in p5.js, the image is loaded right away, and
+    /// an error is returned if it can't be loaded.
+    fn load_image<P: AsRef<std::path::Path>>(&self, path: P) -> image::ImageResult<P5Image>
+    where
+        Self: Sized,
+    {
+        P5Image::load(path)
+    }
+
+    /// Draws an image to the canvas. `x` and `y` set the location of the image, and `w` and `h`
+    /// set its size, which stretches the image to fit. To draw the image at its original size,
+    /// use its [`width`](crate::P5Image::width) and [`height`](crate::P5Image::height). The way
+    /// these parameters are interpreted may be changed with the
+    /// [`image_mode`](crate::P5Trait::image_mode) function.
+    ///
+    /// Images are drawn with the current transformation and blend mode, and are clipped like
+    /// shapes, but the fill and stroke don't affect them.
+    fn image(&mut self, img: &P5Image, x: f32, y: f32, w: f32, h: f32) {
+        let (sw, sh) = (img.width() as f32, img.height() as f32);
+        self.image_region(img, x, y, w, h, 0., 0., sw, sh);
+    }
+
+    /// Draws part of an image to the canvas, like [`image`](crate::P5Trait::image). `sx`, `sy`,
+    /// `sw` and `sh` select the rectangle of the image to draw, in pixels, which is stretched to
+    /// fit the rectangle on the canvas given by `dx`, `dy`, `dw` and `dh`. Parts of the source
+    /// rectangle that are outside of the image are left out.
+    #[allow(clippy::too_many_arguments)]
+    fn image_region(
+        &mut self,
+        img: &P5Image,
+        dx: f32,
+        dy: f32,
+        dw: f32,
+        dh: f32,
+        sx: f32,
+        sy: f32,
+        sw: f32,
+        sh: f32,
+    );
+
+    /// Modifies the location from which images are drawn by changing the way in which parameters
+    /// given to [`image`](crate::P5Trait::image) are interpreted. The default mode is
+    /// `ImageMode::Corner`.
+    fn image_mode(&mut self, mode: ImageMode);
+
+    /// Sets the fill value for displaying images. Images can be tinted to specified colors or made
+    /// transparent by including an alpha value. Each pixel of the image is multiplied by the tint
+    /// color, so tinting with white keeps the image's original colors, and an alpha of 128 makes
+    /// it half transparent. The color is interpreted using the current
+    /// [`color_mode`](crate::P5Trait::color_mode).
+    fn tint<C: IntoColor>(&mut self, color: C);
+
+    /// Removes the current tint set by [`tint`](crate::P5Trait::tint), so that images are drawn
+    /// with their original colors.
+    fn no_tint(&mut self);
+
+    /// Draws images smoothly when they're scaled, by blending between their pixels. This is the
+    /// default. Unlike in p5.js, shapes are always anti-aliased, so this only affects images.
+    fn smooth(&mut self);
+
+    /// Draws images with hard edges between their pixels when they're scaled, which is useful for
+    /// scaling up pixel art. Like in p5.js, this isn't saved by
+    /// [`push`](crate::P5Trait::push).
+    fn no_smooth(&mut self);
+
     fn get_data(&self) -> &[u32];
+
+    /// Loads the pixel data of the canvas into a buffer, which can then be read and changed
+    /// through [`pixels_mut`](crate::P5Trait::pixels_mut). This function must always be called
+    /// before reading from or writing to the pixels, and the changes only appear on the canvas
+    /// once [`update_pixels`](crate::P5Trait::update_pixels) is called.
+    ///
+    /// ```no_run
+    /// # use p5_rs::*;
+    /// # let mut p5 = <P5 as Backend>::new(400, 400);
+    /// p5.load_pixels();
+    /// for pixel in p5.pixels_mut().chunks_exact_mut(4) {
+    ///     pixel[0] = 255 - pixel[0];
+    /// }
+    /// p5.update_pixels();
+    /// ```
+    fn load_pixels(&mut self);
+
+    /// The pixels loaded by [`load_pixels`](crate::P5Trait::load_pixels), which is empty until
+    /// it's called. Each pixel is made up of four values, for its red, green, blue and alpha
+    /// components from 0 to 255, which are _not_ premultiplied by its alpha, unlike
+    /// [`get_data`](crate::P5Trait::get_data). The pixels are stored row by row, starting at the
+    /// top left, so the pixel at `(x, y)` starts at the index `4 * (y * width + x)`.
+    fn pixels_mut(&mut self) -> &mut [u8];
+
+    /// Updates the canvas with the pixels loaded by
+    /// [`load_pixels`](crate::P5Trait::load_pixels), including any changes made to them through
+    /// [`pixels_mut`](crate::P5Trait::pixels_mut) or [`set`](crate::P5Trait::set). Like in p5.js,
+    /// the canvas is replaced directly, so the current transformation, blend mode and clipping
+    /// don't affect it.
+    fn update_pixels(&mut self);
+
+    /// Gets the color of the pixel at `(x, y)` on the canvas, or transparent black if it's outside
+    /// of the canvas. This reads the canvas directly, so it doesn't need
+    /// [`load_pixels`](crate::P5Trait::load_pixels), and it doesn't see changes made to the
+    /// pixels until [`update_pixels`](crate::P5Trait::update_pixels) is called.
+    fn get(&self, x: f32, y: f32) -> Color;
+
+    /// Gets a rectangle of the canvas as an image, with its top left corner at `(x, y)` and the
+    /// width `w` and height `h`. The parts of the rectangle outside of the canvas are transparent.
+    fn get_region(&self, x: f32, y: f32, w: f32, h: f32) -> P5Image;
+
+    /// Changes the color of the pixel at `(x, y)` in the pixels loaded by
+    /// [`load_pixels`](crate::P5Trait::load_pixels), which is called first if the pixels haven't
+    /// been loaded yet. The color is interpreted using the current
+    /// [`color_mode`](crate::P5Trait::color_mode). Like in p5.js, the change only appears on the
+    /// canvas once [`update_pixels`](crate::P5Trait::update_pixels) is called. Pixels outside of
+    /// the canvas are ignored.
+    fn set<C: IntoColor>(&mut self, x: f32, y: f32, color: C);
+
+    /// Saves the current canvas as an image. The format is chosen from the extension of `path`,
+    /// and can be any of PNG, JPEG or BMP. Since JPEG doesn't support transparency, the alpha
+    /// channel is dropped when saving to JPEG.
+    ///
+    /// Unlike [`get_data`](crate::P5Trait::get_data), the saved colors are _not_ premultiplied by
+    /// their alpha.
+    fn save_canvas<P: AsRef<std::path::Path>>(&self, path: P) -> image::ImageResult<()>;
+}
+
+/// Creates a Bézier curve along the x-axis, for evaluating one coordinate of a curve at a time.
+fn bezier_1d(a: f32, b: f32, c: f32, d: f32) -> CubicBezierSegment<f32> {
+    CubicBezierSegment {
+        from: point2(a, 0.),
+        ctrl1: point2(b, 0.),
+        ctrl2: point2(c, 0.),
+        to: point2(d, 0.),
+    }
 }
 
 /// Describes a `RectMode`, which is the location from which rectangles are drawn by changing the way in which parameters given to [`rect`](crate::P5Trait::rect) are interpreted.
@@ -227,6 +675,49 @@ pub enum RectMode {
     Radius,
 }
 
+/// Describes an `EllipseMode`, which is the location from which ellipses, circles and arcs are
+/// drawn by changing the way in which parameters given to [`ellipse`](crate::P5Trait::ellipse)
+/// are interpreted.
+///
+/// The default mode is `EllipseMode::Center`, which interprets the first two parameters as the
+/// shape's center point, while the third and fourth parameters are its width and height.
+///
+/// `EllipseMode::Radius` also uses the first two parameters as the shape's center point, but uses
+/// the third and fourth parameters to specify half of the shape's width and height.
+///
+/// `EllipseMode::Corner` interprets the first two parameters as the upper-left corner of the
+/// shape's bounding box, while the third and fourth parameters are its width and height.
+///
+/// `EllipseMode::Corners` interprets the first two parameters as the location of one corner of
+/// the ellipse's bounding box, and the third and fourth parameters as the location of the
+/// opposite corner.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EllipseMode {
+    Center,
+    Radius,
+    Corner,
+    Corners,
+}
+
+/// Describes an `ImageMode`, which is the location from which images are drawn by changing the
+/// way in which parameters given to [`image`](crate::P5Trait::image) are interpreted.
+///
+/// The default mode is `ImageMode::Corner`, which interprets the first two parameters as the
+/// upper-left corner of the image, while the third and fourth parameters are its width and
+/// height.
+///
+/// `ImageMode::Corners` interprets the first two parameters as the location of one corner of the
+/// image, and the third and fourth parameters as the location of the opposite corner.
+///
+/// `ImageMode::Center` interprets the first two parameters as the image's center point, while
+/// the third and fourth parameters are its width and height.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ImageMode {
+    Corner,
+    Corners,
+    Center,
+}
+
 /// Represents the rounding for each corner of a rectangle
 pub struct RectRounding {
     pub tl: f32,
@@ -252,3 +743,139 @@ impl RectRounding {
         )
     }
 }
+
+/// The unit that angles are measured in, set by [`angle_mode`](crate::P5Trait::angle_mode).
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AngleMode {
+    Radians,
+    Degrees,
+}
+
+impl AngleMode {
+    /// Converts `angle`, measured in this angle mode, into radians.
+    pub fn to_radians(self, angle: f32) -> f32 {
+        match self {
+            AngleMode::Radians => angle,
+            AngleMode::Degrees => angle.to_radians(),
+        }
+    }
+
+    /// Converts `radians` into an angle measured in this angle mode.
+    pub fn from_radians(self, radians: f32) -> f32 {
+        match self {
+            AngleMode::Radians => radians,
+            AngleMode::Degrees => radians.to_degrees(),
+        }
+    }
+}
+
+/// Describes how an [`arc`](crate::P5Trait::arc) is closed.
+///
+/// `ArcMode::Open` leaves the arc's stroke open, which is the default in p5.js. `ArcMode::Chord`
+/// closes the arc with a straight line between its end points, and `ArcMode::Pie` closes it with
+/// lines from each end point to the center of the ellipse, like a slice of pie.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ArcMode {
+    Open,
+    Chord,
+    Pie,
+}
+
+/// Describes how the ends of lines are drawn, set by [`stroke_cap`](crate::P5Trait::stroke_cap).
+///
+/// `StrokeCap::Round` draws a semicircle at each end, which is the default in p5.js.
+/// `StrokeCap::Square` ends the line exactly at its end points, which is the default here, and
+/// `StrokeCap::Project` extends it past them by half of the stroke weight.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StrokeCap {
+    Round,
+    Square,
+    Project,
+}
+
+/// Describes how the segments of lines and the borders of shapes are joined, set by
+/// [`stroke_join`](crate::P5Trait::stroke_join).
+///
+/// `StrokeJoin::Miter` extends the outer edges of the segments until they meet in a point, which
+/// is the default in p5.js. This is limited by [`miter_limit`](crate::P5Trait::miter_limit).
+/// `StrokeJoin::Bevel` cuts the corner off with a straight line, and `StrokeJoin::Round` rounds
+/// it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StrokeJoin {
+    Miter,
+    Bevel,
+    Round,
+}
+
+/// Describes the kind of shape that is made from the vertices given to
+/// [`vertex`](crate::P5Trait::vertex), set by [`begin_shape`](crate::P5Trait::begin_shape).
+///
+/// `ShapeKind::Polygon` connects every vertex into a single shape, which is the default in p5.js.
+///
+/// `ShapeKind::Points` draws each vertex as a point, and `ShapeKind::Lines` draws a line between
+/// each pair of vertices.
+///
+/// `ShapeKind::Triangles` draws a triangle for every three vertices, `ShapeKind::TriangleFan`
+/// draws triangles between the first vertex and each pair of the vertices after it, and
+/// `ShapeKind::TriangleStrip` draws a triangle for every three consecutive vertices.
+///
+/// `ShapeKind::Quads` draws a quad for every four vertices, and `ShapeKind::QuadStrip` draws a
+/// strip of quads, where each pair of vertices after the first two makes a new quad with the
+/// previous pair.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ShapeKind {
+    Polygon,
+    Points,
+    Lines,
+    Triangles,
+    TriangleFan,
+    TriangleStrip,
+    Quads,
+    QuadStrip,
+}
+
+/// Describes whether [`end_shape`](crate::P5Trait::end_shape) connects the last vertex of a
+/// shape back to the first one. This only affects shapes of the kind `ShapeKind::Polygon`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EndMode {
+    Open,
+    Close,
+}
+
+/// Describes how the colors of new shapes are combined with the colors already on the canvas, set
+/// by [`blend_mode`](crate::P5Trait::blend_mode).
+///
+/// `BlendMode::Blend` draws new colors over the old ones, mixing them by the new alpha, which is
+/// the default in p5.js. `BlendMode::Add` adds the colors together, and `BlendMode::Replace`
+/// replaces the old colors, and their alpha, with the new ones. `BlendMode::Remove` erases the
+/// old colors by the alpha of the new ones, without drawing the new colors.
+///
+/// `BlendMode::Darkest` and `BlendMode::Lightest` keep the darker or lighter of the colors.
+/// `BlendMode::Difference` subtracts the darker color from the lighter one, and
+/// `BlendMode::Exclusion` is similar, but with less contrast.
+///
+/// `BlendMode::Multiply` multiplies the colors, which always makes them darker, and
+/// `BlendMode::Screen` does the opposite, which always makes them lighter. `BlendMode::Overlay`
+/// multiplies dark old colors and screens light ones, and `BlendMode::HardLight` does the same
+/// depending on the new colors instead. `BlendMode::SoftLight` is a softer version of
+/// `BlendMode::HardLight`.
+///
+/// `BlendMode::Dodge` lightens the old colors by the new ones, and `BlendMode::Burn` darkens them.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BlendMode {
+    Blend,
+    Add,
+    Darkest,
+    Lightest,
+    Difference,
+    Exclusion,
+    Multiply,
+    Screen,
+    Replace,
+    Remove,
+    Overlay,
+    HardLight,
+    SoftLight,
+    Dodge,
+    Burn,
+}
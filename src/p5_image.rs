@@ -0,0 +1,155 @@
+use crate::backend::pixels::premultiply;
+use crate::p5::ImageMode;
+use crate::Color;
+use euclid::default::Rect;
+use euclid::{point2, rect, size2};
+use std::borrow::Cow;
+#[cfg(feature = "raqote")]
+use std::sync::OnceLock;
+
+/// An image that can be drawn onto the canvas with [`image`](crate::P5Trait::image). Images can
+/// be loaded from PNG, JPEG, BMP and GIF files with
+/// [`load_image`](crate::P5Trait::load_image), or created from an
+/// [`RgbaImage`](image::RgbaImage).
+///
+/// ```no_run
+/// # use p5_rs::*;
+/// # let mut p5 = <P5 as Backend>::new(400, 400);
+/// let img = p5.load_image("cat.png").unwrap();
+/// p5.image(&img, 0., 0., 200., 200.);
+/// ```
+#[derive(Debug, Clone)]
+pub struct P5Image {
+    width: u32,
+    height: u32,
+    /// The RGBA components of each pixel, premultiplied by their alpha, row by row.
+    pub(crate) data: Vec<u8>,
+    /// The pixels in the format that raqote draws, once the image has been drawn with it.
+    #[cfg(feature = "raqote")]
+    argb: OnceLock<Vec<u32>>,
+}
+
+impl PartialEq for P5Image {
+    fn eq(&self, other: &P5Image) -> bool {
+        self.width == other.width && self.height == other.height && self.data == other.data
+    }
+}
+
+impl P5Image {
+    /// Loads an image from a file. The format is chosen from the contents of the file, and can be
+    /// any of PNG, JPEG, BMP or GIF. Only the first frame of an animated GIF is loaded.
+    pub fn load<P: AsRef<std::path::Path>>(path: P) -> image::ImageResult<P5Image> {
+        Ok(P5Image::from(image::open(path)?.into_rgba8()))
+    }
+
+    /// The width of the image, in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the image, in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns the premultiplied RGBA components of each pixel, after multiplying them by the
+    /// `tint` color, if there is one.
+    pub(crate) fn tinted_data(&self, tint: Option<Color>) -> Cow<'_, [u8]> {
+        let tint = match tint {
+            Some(tint) => tint,
+            None => return Cow::Borrowed(&self.data),
+        };
+
+        let a = u32::from(tint.a);
+        let scale = [tint.r, tint.g, tint.b].map(|c| u32::from(c) * a);
+        let mut data = self.data.clone();
+        for pixel in data.chunks_exact_mut(4) {
+            for (c, scale) in pixel.iter_mut().zip(scale) {
+                *c = ((u32::from(*c) * scale + 32512) / 65025) as u8;
+            }
+            pixel[3] = ((u32::from(pixel[3]) * a + 127) / 255) as u8;
+        }
+        Cow::Owned(data)
+    }
+
+    /// Returns each pixel packed into a `u32` as premultiplied ARGB, which is the format that
+    /// raqote draws. The pixels are only converted the first time the image is drawn, since
+    /// sketches usually draw the same images every frame.
+    #[cfg(feature = "raqote")]
+    pub(crate) fn argb_data(&self) -> &[u32] {
+        self.argb.get_or_init(|| to_argb(&self.data))
+    }
+
+    /// Returns where the `source` rectangle of the image ends up when it's stretched over `dest`,
+    /// along with the part of `source` that's inside the image. Like in p5.js, the parts of
+    /// `source` outside of the image are left out, instead of stretching the edges of the image.
+    /// Returns `None` if nothing would be drawn.
+    pub(crate) fn placement(
+        &self,
+        dest: Rect<f32>,
+        source: Rect<f32>,
+    ) -> Option<(Rect<f32>, Rect<f32>)> {
+        let bounds = rect(0., 0., self.width as f32, self.height as f32);
+        let visible = source.intersection(&bounds)?;
+        let scale = (
+            dest.size.width / source.size.width,
+            dest.size.height / source.size.height,
+        );
+        let dest = Rect::new(
+            point2(
+                dest.origin.x + (visible.origin.x - source.origin.x) * scale.0,
+                dest.origin.y + (visible.origin.y - source.origin.y) * scale.1,
+            ),
+            size2(visible.size.width * scale.0, visible.size.height * scale.1),
+        );
+
+        if visible.is_empty() || dest.is_empty() || !dest.size.width.is_finite() {
+            None
+        } else {
+            Some((dest, visible))
+        }
+    }
+}
+
+impl From<image::RgbaImage> for P5Image {
+    fn from(image: image::RgbaImage) -> P5Image {
+        let (width, height) = image.dimensions();
+        let mut data = image.into_raw();
+        for pixel in data.chunks_exact_mut(4) {
+            let premultiplied = premultiply(pixel);
+            pixel.copy_from_slice(&premultiplied);
+        }
+        P5Image {
+            width,
+            height,
+            data,
+            #[cfg(feature = "raqote")]
+            argb: OnceLock::new(),
+        }
+    }
+}
+
+/// Packs the premultiplied RGBA components of each pixel into a `u32` as premultiplied ARGB.
+#[cfg(feature = "raqote")]
+pub(crate) fn to_argb(data: &[u8]) -> Vec<u32> {
+    data.chunks_exact(4)
+        .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+        .collect()
+}
+
+/// Interprets the parameters of an image according to the `mode`, returning the rectangle it's
+/// drawn into. A negative width or height doesn't flip the image, it only moves the rectangle.
+pub(crate) fn image_bounds(mode: ImageMode, x: f32, y: f32, w: f32, h: f32) -> Rect<f32> {
+    let (x1, y1, x2, y2) = match mode {
+        ImageMode::Corner => (x, y, x + w, y + h),
+        ImageMode::Corners => (x, y, w, h),
+        ImageMode::Center => (x - w / 2., y - h / 2., x + w / 2., y + h / 2.),
+    };
+    rect(x1.min(x2), y1.min(y2), (x2 - x1).abs(), (y2 - y1).abs())
+}
+
+/// Returns the rectangle given by `x`, `y`, `w` and `h`, flipped so that its width and height
+/// aren't negative.
+pub(crate) fn source_bounds(x: f32, y: f32, w: f32, h: f32) -> Rect<f32> {
+    image_bounds(ImageMode::Corner, x, y, w, h)
+}
@@ -1,17 +1,22 @@
-use crate::p5::P5 as P5Trait;
-use crate::{Key, P5};
+use crate::backend::Backend;
+use crate::Key;
 use minifb::{Window, WindowOptions};
+use std::path::Path;
 
-pub trait Sketch {
+/// A sketch, drawn by the backend `B`. By default, sketches are drawn with [`P5`](crate::P5),
+/// which is chosen with cargo features. A sketch can instead implement `Sketch<B>` for a specific
+/// backend, or for every backend, e.g. `impl<B: Backend> Sketch<B> for MySketch`, and then be run
+/// with whichever backend is needed, e.g. `Sketch::<TinySkiaP5>::run(&mut MySketch)`.
+pub trait Sketch<B: Backend = crate::P5> {
     /// The setup() function is called once when the program starts. It's used to define
     /// initial environment properties such as screen size and background color and to load
     /// media such as images and fonts as the program starts. There can only be one setup()
     /// function for each program and it shouldn't be called again after its initial execution.
     ///
     /// The default implementation of [`setup`](crate::sketch::Sketch::setup) is empty.
-    fn setup(&mut self, _p5: &mut P5) {}
+    fn setup(&mut self, _p5: &mut B) {}
 
-    fn draw(&mut self, _p5: &mut P5) {}
+    fn draw(&mut self, _p5: &mut B) {}
 
     fn title(&self) -> &'static str {
         "p5-rs Window"
@@ -25,9 +30,9 @@ pub trait Sketch {
         400
     }
 
-    fn key_pressed(&mut self, _p5: &mut P5, _keys: Vec<Key>) {}
-    fn key_released(&mut self, _p5: &mut P5, _keys: Vec<Key>) {}
-    fn key_typed(&mut self, _p5: &mut P5, _chars: Vec<char>) {}
+    fn key_pressed(&mut self, _p5: &mut B, _keys: Vec<Key>) {}
+    fn key_released(&mut self, _p5: &mut B, _keys: Vec<Key>) {}
+    fn key_typed(&mut self, _p5: &mut B, _chars: Vec<char>) {}
 
     fn run(&mut self)
     where
@@ -43,28 +48,30 @@ pub trait Sketch {
             panic!("{}", e);
         });
 
-        let mut p5 = P5::new(self);
+        let mut p5 = B::new(self.width(), self.height());
         self.setup(&mut p5);
 
         // Limit to max ~60 fps update rate
         window.limit_update_rate(Some(std::time::Duration::from_micros(
-            (1_000_000. / p5.frame_rate) as u64,
+            (1_000_000. / p5.target_frame_rate()) as u64,
         )));
 
         while window.is_open() && !window.is_key_down(Key::Escape) {
-            // TODO: This isn't specified in the p5 trait, why can we assume it exists?
-            p5.frame_count += 1;
-            p5.reset_matrix();
+            p5.begin_frame();
 
-            p5.keys = window
+            let keys = window
                 .get_keys()
                 .and_then(|keys| if keys.len() == 0 { Some(keys) } else { None });
 
-            if let Some(ref keys) = p5.keys {
-                if keys.len() > 0 {
-                    p5.key_code = Some(keys[keys.len() - 1]);
-                }
-            }
+            // TODO: Deal with Caps Lock
+            let caps = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+            let chars: Vec<_> = keys
+                .iter()
+                .flatten()
+                .filter_map(|&k| key_to_char(k, caps))
+                .collect();
+
+            p5.set_keys(keys);
 
             if let Some(keys_pressed) = window.get_keys_pressed(minifb::KeyRepeat::No) {
                 if keys_pressed.len() > 0 {
@@ -78,15 +85,6 @@ pub trait Sketch {
                 }
             }
 
-            // TODO: Deal with Caps Lock
-            let caps = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
-            let chars: Vec<_> = p5
-                .keys
-                .iter()
-                .flatten()
-                .filter_map(|&k| key_to_char(k, caps))
-                .collect();
-
             if chars.len() > 0 {
                 self.key_typed(&mut p5, chars);
             }
@@ -99,6 +97,107 @@ pub trait Sketch {
                 .unwrap();
         }
     }
+
+    /// Runs the sketch without opening a window. `setup` is called once, followed by `frames`
+    /// calls to `draw`, and the backend holding the final framebuffer is returned, so
+    /// the result can be inspected with [`get_data`](crate::P5Trait::get_data). No display is
+    /// required, which makes this suitable for tests and server-side rendering.
+    ///
+    /// Since there is no window, the keyboard callbacks are never called, and frames are drawn
+    /// as fast as possible, regardless of the `frame_rate`.
+    fn run_headless(&mut self, frames: usize) -> B
+    where
+        Self: std::marker::Sized,
+    {
+        match run_frames(self, frames, |_| Ok::<_, std::convert::Infallible>(())) {
+            Ok(p5) => p5,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Renders `count` frames of the sketch without opening a window, and saves each one into
+    /// `dir` as a PNG named `<prefix>-<frame>.png`, where `<frame>` is the zero-padded
+    /// `frame_count`. `dir` is created if it doesn't already exist.
+    ///
+    /// Every call to `draw` produces exactly one file, no matter how long it takes to draw, so
+    /// animations driven by `frame_count` render deterministically. This is useful for rendering
+    /// animations offline, and assembling them into a video afterwards.
+    fn save_frames<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+        prefix: &str,
+        count: usize,
+    ) -> image::ImageResult<()>
+    where
+        Self: std::marker::Sized,
+    {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+
+        run_frames(self, count, |p5| {
+            p5.save_canvas(dir.join(format!("{}-{:05}.png", prefix, p5.frame_count())))
+        })?;
+        Ok(())
+    }
+
+    /// Renders `frames` frames of the sketch without opening a window, and saves them to `path`
+    /// as an animated GIF that loops forever. The colors of each frame are quantized down to a
+    /// 256 color palette.
+    ///
+    /// The delay between frames is derived from the `frame_rate`. Note that GIFs store delays in
+    /// hundredths of a second, so the frame rate gets rounded, and that many viewers slow down
+    /// GIFs with frame rates above 50 fps. A GIF can't be paused, so if the frame rate is zero or
+    /// negative, the default of 60 fps is used instead.
+    fn save_gif<P: AsRef<Path>>(&mut self, path: P, frames: usize) -> image::ImageResult<()>
+    where
+        Self: std::marker::Sized,
+    {
+        use image::codecs::gif::{GifEncoder, Repeat};
+
+        let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        // Speed 10 is the quantization speed recommended by the `gif` crate, speed 1 gives
+        // marginally better palettes, but is far too slow for more than a couple of frames.
+        let mut encoder = GifEncoder::new_with_speed(file, 10);
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        run_frames(self, frames, |p5| {
+            let fps = match p5.target_frame_rate() {
+                fps if fps > 0. => fps,
+                _ => 60.,
+            };
+            // Delays longer than the GIF maximum of about 11 minutes get saturated anyway, so
+            // clamping them first keeps tiny frame rates from overflowing the `Duration`.
+            let delay = std::time::Duration::from_secs_f32((1. / fps).min(1000.));
+            encoder.encode_frame(image::Frame::from_parts(
+                p5.to_image(),
+                0,
+                0,
+                image::Delay::from_saturating_duration(delay),
+            ))
+        })?;
+        Ok(())
+    }
+}
+
+/// Runs `setup` once, and then `draw` for the given number of `frames`, without opening a window.
+/// `after_draw` is called after every frame, and if it returns an error, rendering stops early.
+fn run_frames<B, S, F, E>(sketch: &mut S, frames: usize, mut after_draw: F) -> Result<B, E>
+where
+    B: Backend,
+    S: Sketch<B>,
+    F: FnMut(&B) -> Result<(), E>,
+{
+    let mut p5 = B::new(sketch.width(), sketch.height());
+    sketch.setup(&mut p5);
+
+    for _ in 0..frames {
+        p5.begin_frame();
+        sketch.draw(&mut p5);
+        p5.end_frame();
+        after_draw(&p5)?;
+    }
+
+    Ok(p5)
 }
 
 /// Attempts to convert a `Key` into the corresponding character, because minifb doesn't expose a
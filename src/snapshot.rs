@@ -0,0 +1,98 @@
+use crate::backend::Backend;
+use crate::Sketch;
+use image::{Rgba, RgbaImage};
+use std::path::Path;
+
+/// The environment variable which, when set, makes [`assert_snapshot`] overwrite the reference
+/// images with the current output instead of comparing against them.
+pub const UPDATE_SNAPSHOTS_VAR: &str = "P5_UPDATE_SNAPSHOTS";
+
+/// Runs `sketch` without opening a window for the given number of `frames`, and compares the
+/// final canvas against the reference PNG at `reference`. This is meant to be used in tests, to
+/// check that a sketch still draws exactly what it used to.
+///
+/// The comparison fails if any channel of any pixel differs from the reference by more than
+/// `tolerance`. In that case, an image highlighting the mismatched pixels in red is written next
+/// to the reference (with the extension `.diff.png`), and this function panics.
+///
+/// If the `P5_UPDATE_SNAPSHOTS` environment variable is set, the reference image is
+/// (re)generated from the current output instead, e.g. with `P5_UPDATE_SNAPSHOTS=1 cargo test`.
+pub fn assert_snapshot<B, S, P>(sketch: &mut S, frames: usize, reference: P, tolerance: u8)
+where
+    B: Backend,
+    S: Sketch<B>,
+    P: AsRef<Path>,
+{
+    let reference = reference.as_ref();
+    let actual = sketch.run_headless(frames).to_image();
+
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() {
+        if let Some(dir) = reference.parent() {
+            std::fs::create_dir_all(dir).unwrap_or_else(|e| {
+                panic!(
+                    "Failed to create snapshot directory {}: {}",
+                    dir.display(),
+                    e
+                )
+            });
+        }
+        actual.save(reference).unwrap_or_else(|e| {
+            panic!(
+                "Failed to write reference image {}: {}",
+                reference.display(),
+                e
+            )
+        });
+        return;
+    }
+
+    let expected = image::open(reference)
+        .unwrap_or_else(|e| {
+            panic!(
+                "Failed to open reference image {}: {}. Run with `{}=1` to create it.",
+                reference.display(),
+                e,
+                UPDATE_SNAPSHOTS_VAR
+            )
+        })
+        .to_rgba8();
+
+    assert_eq!(
+        actual.dimensions(),
+        expected.dimensions(),
+        "The sketch's size doesn't match the reference image {}",
+        reference.display()
+    );
+
+    let mut mismatched = 0;
+    let diff = RgbaImage::from_fn(actual.width(), actual.height(), |x, y| {
+        let (a, e) = (actual.get_pixel(x, y), expected.get_pixel(x, y));
+        if a.0
+            .iter()
+            .zip(&e.0)
+            .any(|(a, e)| a.abs_diff(*e) > tolerance)
+        {
+            mismatched += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            // Show matching pixels as a faded grayscale version of the reference, so the
+            // mismatches stand out, but it's still clear what was being drawn.
+            let gray = (u16::from(e[0]) + u16::from(e[1]) + u16::from(e[2])) / 12 + 192;
+            Rgba([gray as u8, gray as u8, gray as u8, 255])
+        }
+    });
+
+    if mismatched > 0 {
+        let diff_path = reference.with_extension("diff.png");
+        diff.save(&diff_path).unwrap_or_else(|e| {
+            panic!("Failed to write diff image {}: {}", diff_path.display(), e)
+        });
+        panic!(
+            "{} pixels differ from the reference image {} by more than {}. The differences are shown in {}",
+            mismatched,
+            reference.display(),
+            tolerance,
+            diff_path.display()
+        );
+    }
+}
@@ -1,5 +1,18 @@
 use p5_rs::*;
 
+/// Returns the path of the reference image called `name`. Each backend anti-aliases slightly
+/// differently, so they each have their own set of reference images.
+fn snapshot(name: &str) -> std::path::PathBuf {
+    #[cfg(feature = "raqote")]
+    let backend = "raqote";
+    #[cfg(all(feature = "tiny-skia", not(feature = "raqote")))]
+    let backend = "tiny-skia";
+
+    std::path::Path::new("tests/snapshots")
+        .join(backend)
+        .join(format!("{}.png", name))
+}
+
 #[test]
 fn basic() {
     struct BasicTest;
@@ -9,7 +22,26 @@ fn basic() {
         fn draw(&mut self, _p5: &mut P5) {}
     }
 
-    BasicTest.run();
+    BasicTest.run_headless(1);
+}
+
+#[cfg(feature = "raqote")]
+#[test]
+fn raqote_new() {
+    struct SizeTest;
+
+    impl Sketch<RaqoteP5> for SizeTest {
+        fn width(&self) -> usize {
+            120
+        }
+
+        fn height(&self) -> usize {
+            80
+        }
+    }
+
+    let p5 = RaqoteP5::new(&SizeTest);
+    assert_eq!(p5.get_data().len(), 120 * 80);
 }
 
 #[test]
@@ -22,7 +54,7 @@ fn background() {
         }
     }
 
-    BackgroundTest.run();
+    BackgroundTest.run_headless(1);
 }
 
 #[test]
@@ -37,7 +69,7 @@ fn changing_background() {
         }
     }
 
-    BackgroundTest(0.).run();
+    BackgroundTest(0.).run_headless(60);
 }
 
 #[test]
@@ -51,7 +83,7 @@ fn ellipse() {
         }
     }
 
-    EllipseTest.run();
+    assert_snapshot(&mut EllipseTest, 1, snapshot("ellipse"), 2);
 }
 
 #[test]
@@ -65,7 +97,46 @@ fn line() {
         }
     }
 
-    LineTest.run();
+    assert_snapshot(&mut LineTest, 1, snapshot("line"), 2);
+}
+
+#[test]
+fn line_transform() {
+    use std::f32::consts::FRAC_PI_2;
+
+    struct LineTransformTest;
+
+    impl Sketch for LineTransformTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.stroke((255., 0., 0., 127.));
+            p5.stroke_weight(10.);
+            p5.stroke_cap(StrokeCap::Project);
+
+            // Like in p5, each transformation applies inside the ones before it, so the line is
+            // rotated about its own start and then moved to (100, 100).
+            p5.translate(100., 100.);
+            p5.rotate(FRAC_PI_2);
+            p5.line(0., 0., 50., 0.);
+
+            // Rotating first turns the direction that the translation moves in, which puts this
+            // line at (-100, 100), off the canvas.
+            p5.reset_matrix();
+            p5.rotate(FRAC_PI_2);
+            p5.translate(100., 100.);
+            p5.line(0., 0., 50., 0.);
+        }
+    }
+
+    let p5: P5 = LineTransformTest.run_headless(0);
+    let image = p5.to_image();
+    // The line is drawn from (100, 100) down to (100, 150), and is half transparent.
+    let [r, g, b, a] = image.get_pixel(100, 125).0;
+    assert!((126..=128).contains(&r) && g == 0 && b == 0 && a == 255);
+    // The projecting cap extends the line past its end by half the stroke weight.
+    assert_ne!(image.get_pixel(100, 153).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(100, 158).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(125, 100).0, [0, 0, 0, 255]);
 }
 
 #[test]
@@ -82,7 +153,94 @@ fn point() {
         }
     }
 
-    PointTest.run();
+    assert_snapshot(&mut PointTest, 1, snapshot("point"), 2);
+}
+
+#[test]
+fn points() {
+    struct PointsTest(bool);
+
+    impl Sketch for PointsTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.stroke((255., 0., 0., 127.));
+            p5.point(10., 10.);
+            // A point between two pixels covers half of each of them.
+            p5.stroke((255., 0., 0.));
+            p5.point(20.5, 10.);
+            // Partly transparent points are blended with what's under them.
+            p5.stroke(100);
+            p5.point(30., 10.);
+            p5.stroke((0., 200., 0., 128.));
+            p5.point(30., 10.);
+
+            // Points outside of the canvas, or that aren't finite, are ignored.
+            p5.point(-5., -5.);
+            p5.point(400., 399.);
+            p5.point(1e20, 10.);
+            p5.point(f32::NAN, f32::INFINITY);
+
+            p5.translate(50., 50.);
+            p5.stroke(255);
+            let points: Vec<(f32, f32)> = (0..1000)
+                .map(|i| ((i % 100) as f32 * 3., (i / 100) as f32 * 10.))
+                .collect();
+            if self.0 {
+                p5.points(&points);
+            } else {
+                for &(x, y) in &points {
+                    p5.point(x, y);
+                }
+            }
+        }
+    }
+
+    let p5: P5 = PointsTest(true).run_headless(0);
+    let image = p5.to_image();
+    let [r, g, b, _] = image.get_pixel(10, 10).0;
+    assert!((126..=128).contains(&r) && g == 0 && b == 0);
+    assert_eq!(image.get_pixel(9, 10).0, [0, 0, 0, 255]);
+    let [r, g, b, _] = image.get_pixel(20, 10).0;
+    assert!((126..=128).contains(&r) && g == 0 && b == 0);
+    let [r, g, b, _] = image.get_pixel(21, 10).0;
+    assert!((126..=128).contains(&r) && g == 0 && b == 0);
+    let [r, g, b, _] = image.get_pixel(30, 10).0;
+    assert!((49..=51).contains(&r) && (149..=151).contains(&g) && (49..=51).contains(&b));
+    assert_eq!(image.get_pixel(399, 399).0, [0, 0, 0, 255]);
+
+    assert_eq!(image.get_pixel(50, 50).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(347, 140).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(348, 140).0, [0, 0, 0, 255]);
+
+    let separate: P5 = PointsTest(false).run_headless(0);
+    assert!(image == separate.to_image());
+}
+
+#[test]
+fn point_weights() {
+    struct WeightTest(f32);
+
+    impl Sketch for WeightTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.stroke(255);
+            p5.stroke_weight(self.0);
+            p5.point(-1e6, 200.);
+            p5.point(200., 200.);
+        }
+    }
+
+    // A huge dot only has to work out the coverage of the pixels on the canvas.
+    let p5: P5 = WeightTest(1e9).run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(399, 399).0, [255, 255, 255, 255]);
+
+    // Dots whose size is negative or isn't finite are ignored.
+    for &weight in &[f32::INFINITY, f32::NAN, -5., -0.5] {
+        let p5: P5 = WeightTest(weight).run_headless(0);
+        assert_eq!(p5.to_image().get_pixel(200, 200).0, [0, 0, 0, 255]);
+    }
 }
 
 #[test]
@@ -114,7 +272,7 @@ fn stroke() {
         }
     }
 
-    StrokeTest.run();
+    assert_snapshot(&mut StrokeTest, 1, snapshot("stroke"), 2);
 }
 
 #[test]
@@ -132,7 +290,7 @@ fn quad() {
         }
     }
 
-    QuadTest.run();
+    assert_snapshot(&mut QuadTest, 1, snapshot("quad"), 2);
 }
 
 #[test]
@@ -171,7 +329,33 @@ fn rect() {
         }
     }
 
-    RectTest.run();
+    assert_snapshot(&mut RectTest, 1, snapshot("rect"), 2);
+}
+
+#[test]
+fn rect_corners() {
+    struct RectCornersTest;
+
+    impl Sketch for RectCornersTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.no_stroke();
+            p5.fill(255);
+            // Corners without any rounding are left sharp, even when the others are rounded.
+            p5.rect(0., 0., 100., 100., RectRounding::new(0., 40., 0., 40.));
+            // Each shape starts from its own first point, not from where the last shape started.
+            p5.rect(200., 200., 50., 50., None);
+        }
+    }
+
+    let p5 = RectCornersTest.run_headless(0);
+    let pixel = |x: usize, y: usize| p5.get_data()[y * 400 + x];
+    assert_eq!(pixel(1, 1), 0xffffffff);
+    assert_eq!(pixel(98, 1), 0xff000000);
+    assert_eq!(pixel(98, 98), 0xffffffff);
+    assert_eq!(pixel(1, 98), 0xff000000);
+    assert_eq!(pixel(225, 225), 0xffffffff);
+    assert_eq!(pixel(150, 150), 0xff000000);
 }
 
 #[test]
@@ -185,7 +369,825 @@ fn triangle() {
         }
     }
 
-    TriangleTest.run();
+    assert_snapshot(&mut TriangleTest, 1, snapshot("triangle"), 2);
+}
+
+#[test]
+fn shapes() {
+    struct ShapesTest;
+
+    impl Sketch for ShapesTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+
+            let kinds = [
+                ShapeKind::Polygon,
+                ShapeKind::Points,
+                ShapeKind::Lines,
+                ShapeKind::Triangles,
+                ShapeKind::TriangleFan,
+                ShapeKind::TriangleStrip,
+                ShapeKind::Quads,
+                ShapeKind::QuadStrip,
+            ];
+            for (i, kind) in kinds.iter().enumerate() {
+                let (x, y) = ((i % 4) as f32 * 100. + 10., (i / 4) as f32 * 100. + 10.);
+                p5.stroke_weight(if *kind == ShapeKind::Points { 5. } else { 1. });
+                p5.begin_shape(*kind);
+                p5.vertex(x, y);
+                p5.vertex(x, y + 80.);
+                p5.vertex(x + 40., y);
+                p5.vertex(x + 40., y + 80.);
+                p5.vertex(x + 80., y);
+                p5.vertex(x + 80., y + 80.);
+                p5.end_shape(EndMode::Close);
+            }
+
+            p5.fill((255., 0., 0.));
+            p5.begin_shape(ShapeKind::Polygon);
+            p5.vertex(50., 250.);
+            p5.vertex(150., 250.);
+            p5.vertex(100., 350.);
+            p5.end_shape(EndMode::Open);
+        }
+    }
+
+    assert_snapshot(&mut ShapesTest, 1, snapshot("shapes"), 2);
+}
+
+#[test]
+fn curves() {
+    struct CurvesTest;
+
+    impl Sketch for CurvesTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+            p5.no_fill();
+            p5.bezier(85., 20., 10., 10., 90., 90., 15., 80.);
+            p5.curve(5., 26., 73., 24., 73., 61., 15., 65.);
+
+            p5.curve_tightness(1.);
+            p5.curve(105., 26., 173., 24., 173., 61., 115., 65.);
+            p5.curve_tightness(0.);
+
+            p5.fill((255., 0., 0.));
+            p5.begin_shape(ShapeKind::Polygon);
+            p5.vertex(230., 20.);
+            p5.bezier_vertex(280., 0., 280., 75., 230., 75.);
+            p5.quadratic_vertex(200., 50., 230., 20.);
+            p5.end_shape(EndMode::Close);
+
+            p5.begin_shape(ShapeKind::Polygon);
+            p5.curve_vertex(84., 191.);
+            p5.curve_vertex(84., 191.);
+            p5.curve_vertex(68., 119.);
+            p5.curve_vertex(21., 117.);
+            p5.curve_vertex(32., 200.);
+            p5.curve_vertex(32., 200.);
+            p5.end_shape(EndMode::Open);
+
+            assert_eq!(p5.bezier_point(85., 10., 90., 15., 0.5), 50.);
+            assert_eq!(p5.bezier_tangent(0., 0., 10., 10., 0.), 0.);
+            assert_eq!(p5.curve_point(5., 73., 73., 15., 0.), 73.);
+            assert_eq!(p5.curve_point(0., 10., 20., 30., 0.5), 15.);
+        }
+    }
+
+    assert_snapshot(&mut CurvesTest, 1, snapshot("curves"), 2);
+}
+
+#[test]
+fn arc() {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    struct ArcTest;
+
+    impl Sketch for ArcTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+            p5.arc(50., 55., 50., 50., 0., FRAC_PI_2, ArcMode::Open);
+            p5.no_fill();
+            p5.arc(50., 55., 60., 60., FRAC_PI_2, PI, ArcMode::Open);
+            p5.arc(50., 55., 70., 70., PI, PI + 0.8, ArcMode::Open);
+            p5.arc(50., 55., 80., 80., PI + 0.8, 2. * PI, ArcMode::Open);
+
+            p5.fill(255);
+            p5.arc(200., 50., 120., 60., 0., PI + 0.8, ArcMode::Open);
+            p5.arc(200., 150., 120., 60., 0., PI + 0.8, ArcMode::Chord);
+            p5.arc(200., 250., 120., 60., 0., PI + 0.8, ArcMode::Pie);
+
+            // The arc wraps around when `stop` is less than `start`.
+            p5.arc(330., 100., 80., 120., 7. * PI / 4., FRAC_PI_2, ArcMode::Pie);
+            p5.arc(330., 250., 80., 80., 0., 3. * PI, ArcMode::Pie);
+        }
+    }
+
+    assert_snapshot(&mut ArcTest, 1, snapshot("arc"), 2);
+}
+
+#[test]
+fn ellipse_mode() {
+    struct EllipseModeTest;
+
+    impl Sketch for EllipseModeTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+
+            p5.ellipse_mode(EllipseMode::Radius);
+            p5.fill(255);
+            p5.ellipse(50., 50., 30., 30.);
+            p5.ellipse_mode(EllipseMode::Center);
+            p5.fill(100);
+            p5.ellipse(50., 50., 30., 30.);
+
+            p5.ellipse_mode(EllipseMode::Corner);
+            p5.fill(255);
+            p5.ellipse(125., 25., 50., 50.);
+            p5.ellipse_mode(EllipseMode::Corners);
+            p5.fill(100);
+            p5.ellipse(125., 25., 150., 50.);
+
+            p5.ellipse_mode(EllipseMode::Corner);
+            p5.circle(225., 25., 50.);
+            p5.arc(300., 25., 50., 50., 0., std::f32::consts::PI, ArcMode::Pie);
+        }
+    }
+
+    assert_snapshot(&mut EllipseModeTest, 1, snapshot("ellipse_mode"), 2);
+}
+
+#[test]
+fn stroke_styles() {
+    struct StrokeStylesTest;
+
+    impl Sketch for StrokeStylesTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+            p5.stroke_weight(12.);
+
+            p5.stroke_cap(StrokeCap::Round);
+            p5.line(40., 40., 160., 40.);
+            p5.stroke_cap(StrokeCap::Square);
+            p5.line(40., 80., 160., 80.);
+            p5.stroke_cap(StrokeCap::Project);
+            p5.line(40., 120., 160., 120.);
+
+            p5.no_fill();
+            p5.stroke_join(StrokeJoin::Miter);
+            p5.triangle(240., 60., 280., 20., 320., 60.);
+            p5.stroke_join(StrokeJoin::Bevel);
+            p5.triangle(240., 120., 280., 80., 320., 120.);
+            p5.stroke_join(StrokeJoin::Round);
+            p5.triangle(240., 180., 280., 140., 320., 180.);
+            // With a low enough miter limit, the sharp corner is beveled instead.
+            p5.stroke_join(StrokeJoin::Miter);
+            p5.miter_limit(1.5);
+            p5.triangle(40., 180., 100., 160., 160., 180.);
+
+            p5.stroke_weight(4.);
+            p5.stroke_cap(StrokeCap::Square);
+            p5.stroke_dash(&[20., 10.], 0.);
+            p5.line(40., 240., 360., 240.);
+            p5.stroke_dash(&[20., 10.], 15.);
+            p5.line(40., 270., 360., 270.);
+            // An odd number of lengths is repeated, so this is 20 on, 5 off, 5 on, 20 off, 5 on, 5 off.
+            p5.stroke_dash(&[20., 5., 5.], 0.);
+            p5.rect(40., 300., 140., 60., None);
+            // An invalid pattern is ignored, and an empty one goes back to a solid stroke.
+            p5.stroke_dash(&[-1., 5.], 0.);
+            p5.ellipse(270., 330., 120., 60.);
+            p5.stroke_dash(&[], 0.);
+            p5.line(40., 385., 360., 385.);
+        }
+    }
+
+    assert_snapshot(&mut StrokeStylesTest, 1, snapshot("stroke_styles"), 2);
+}
+
+#[test]
+fn gradients() {
+    use std::f32::consts::FRAC_PI_4;
+
+    struct GradientsTest;
+
+    impl Sketch for GradientsTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+            p5.no_stroke();
+
+            let linear = Gradient::linear(20., 0., 180., 0.)
+                .color_stop(0., (255., 0., 0.))
+                .color_stop(0.5, (255., 255., 0.))
+                .color_stop(1., (0., 0., 255., 0.));
+            p5.fill_gradient(&linear);
+            p5.rect(10., 10., 180., 80., None);
+
+            // Stops don't have to be added in order, and stops at the same offset give a hard edge.
+            let radial = Gradient::radial(300., 60., 50.)
+                .color_stop(1., (0., 0., 0.))
+                .color_stop(0., (255., 255., 255.))
+                .color_stop(0.5, (0., 200., 0.))
+                .color_stop(0.5, (0., 0., 200.));
+            p5.fill_gradient(&radial);
+            p5.circle(300., 60., 100.);
+
+            let conic = Gradient::conic(100., 200., FRAC_PI_4)
+                .color_mode(HSB)
+                .color_stop(0., (0., 100., 100.))
+                .color_stop(1. / 3., (120., 100., 100.))
+                .color_stop(2. / 3., (240., 100., 100.))
+                .color_stop(1., (360., 100., 100.));
+            p5.fill_gradient(&conic);
+            p5.circle(100., 200., 140.);
+
+            // The gradient is transformed along with the shape.
+            p5.push();
+            p5.translate(300., 200.);
+            p5.rotate(FRAC_PI_4);
+            p5.fill_gradient(
+                &Gradient::linear(-50., 0., 50., 0.)
+                    .color_stop(0., 0)
+                    .color_stop(1., 255),
+            );
+            p5.rect_mode(RectMode::Center);
+            p5.rect(0., 0., 100., 100., None);
+            p5.pop();
+
+            p5.no_fill();
+            p5.stroke_weight(12.);
+            p5.stroke_gradient(
+                &Gradient::linear(20., 0., 380., 0.)
+                    .color_stop(0., (255., 0., 255.))
+                    .color_stop(1., (0., 255., 255.)),
+            );
+            p5.line(20., 300., 380., 300.);
+            p5.stroke_gradient(&conic);
+            p5.ellipse(200., 350., 300., 50.);
+
+            p5.stroke(0);
+            p5.stroke_weight(2.);
+            p5.fill((255., 0., 0.));
+            p5.rect(180., 170., 30., 30., None);
+        }
+    }
+
+    assert_snapshot(&mut GradientsTest, 1, snapshot("gradients"), 2);
+}
+
+#[test]
+fn degenerate_gradients() {
+    struct DegenerateTest;
+
+    impl Sketch for DegenerateTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.no_stroke();
+            let stops = |gradient: Gradient| {
+                gradient
+                    .color_stop(0., (255., 0., 0.))
+                    .color_stop(1., (0., 0., 255.))
+            };
+            p5.fill_gradient(&stops(Gradient::linear(50., 50., 50., 50.)));
+            p5.rect(0., 0., 100., 100., None);
+            p5.fill_gradient(&stops(Gradient::radial(150., 50., 0.)));
+            p5.rect(100., 0., 100., 100., None);
+        }
+    }
+
+    // A gradient with no size is the color of its first stop everywhere.
+    let p5: P5 = DegenerateTest.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(80, 80).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(180, 80).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn blend_modes() {
+    struct BlendModesTest;
+
+    impl Sketch for BlendModesTest {
+        fn setup(&mut self, p5: &mut P5) {
+            let modes = [
+                BlendMode::Blend,
+                BlendMode::Add,
+                BlendMode::Darkest,
+                BlendMode::Lightest,
+                BlendMode::Difference,
+                BlendMode::Exclusion,
+                BlendMode::Multiply,
+                BlendMode::Screen,
+                BlendMode::Replace,
+                BlendMode::Remove,
+                BlendMode::Overlay,
+                BlendMode::HardLight,
+                BlendMode::SoftLight,
+                BlendMode::Dodge,
+                BlendMode::Burn,
+            ];
+
+            p5.background((40., 80., 120.));
+            for (i, &mode) in modes.iter().enumerate() {
+                let (x, y) = ((i % 4) as f32 * 100., (i / 4) as f32 * 100.);
+                p5.no_stroke();
+                p5.fill((230., 190., 60.));
+                p5.rect(x + 10., y + 10., 40., 80., None);
+
+                p5.push();
+                p5.blend_mode(mode);
+                p5.fill((200., 40., 160., 200.));
+                p5.circle(x + 50., y + 50., 60.);
+                p5.stroke((90., 220., 90.));
+                p5.stroke_weight(6.);
+                p5.line(x + 10., y + 85., x + 90., y + 85.);
+                p5.point(x + 80., y + 20.);
+                p5.pop();
+            }
+        }
+    }
+
+    assert_snapshot(&mut BlendModesTest, 1, snapshot("blend_modes"), 2);
+
+    struct BlendPixelsTest;
+
+    impl Sketch for BlendPixelsTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background((200., 100., 50.));
+            p5.no_stroke();
+            p5.blend_mode(BlendMode::Multiply);
+            p5.fill((128., 255., 0.));
+            p5.rect(0., 0., 50., 50., None);
+
+            p5.push();
+            p5.blend_mode(BlendMode::Replace);
+            p5.fill((0., 0., 255., 102.));
+            p5.rect(50., 0., 50., 50., None);
+            // Only the dots replace the canvas, not the rest of the area around them.
+            p5.stroke((255., 0., 0., 128.));
+            p5.stroke_weight(5.);
+            p5.points(&[(150., 25.)]);
+            p5.no_stroke();
+
+            p5.blend_mode(BlendMode::Remove);
+            p5.fill(0);
+            p5.rect(200., 0., 50., 50., None);
+            p5.pop();
+
+            // The blend mode is restored by `pop`.
+            p5.fill((128., 255., 0.));
+            p5.rect(250., 0., 50., 50., None);
+        }
+    }
+
+    let p5: P5 = BlendPixelsTest.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(25, 25).0, [100, 100, 0, 255]);
+    assert_eq!(image.get_pixel(75, 25).0, [0, 0, 255, 102]);
+    assert_eq!(image.get_pixel(150, 25).0, [255, 0, 0, 128]);
+    assert_eq!(image.get_pixel(147, 22).0, [200, 100, 50, 255]);
+    assert_eq!(image.get_pixel(225, 25).0, [0, 0, 0, 0]);
+    assert_eq!(image.get_pixel(275, 25).0, [100, 100, 0, 255]);
+}
+
+#[test]
+fn erase() {
+    struct EraseTest;
+
+    impl Sketch for EraseTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background((200., 100., 50.));
+            p5.blend_mode(BlendMode::Multiply);
+            p5.fill((255., 0., 0.));
+            p5.no_stroke();
+
+            p5.erase(255., 255.);
+            p5.rect(0., 0., 50., 50., None);
+            p5.erase(102., 255.);
+            p5.rect(50., 0., 50., 50., None);
+
+            // The fill and stroke erase with their own strengths.
+            p5.erase(0., 255.);
+            p5.stroke(0);
+            p5.stroke_weight(10.);
+            p5.rect(120., 10., 60., 30., None);
+            p5.points(&[(225., 25.)]);
+            p5.no_stroke();
+
+            // Erasing is saved by `push`, and restored by `pop`.
+            p5.erase(255., 255.);
+            p5.push();
+            p5.no_erase();
+            p5.rect(250., 0., 50., 50., None);
+            p5.pop();
+            p5.rect(300., 0., 50., 50., None);
+
+            // The fill color and blend mode are used again after `no_erase`.
+            p5.no_erase();
+            p5.fill((128., 255., 0.));
+            p5.rect(350., 0., 50., 50., None);
+        }
+    }
+
+    let p5: P5 = EraseTest.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(25, 25).0, [0, 0, 0, 0]);
+    let [_, _, _, a] = image.get_pixel(75, 25).0;
+    assert!((152..=154).contains(&a), "alpha {}", a);
+    // Erasing with a strength of 0 leaves the canvas as it was, apart from rounding.
+    let [_, _, _, a] = image.get_pixel(150, 25).0;
+    assert!(a >= 254, "alpha {}", a);
+    assert_eq!(image.get_pixel(120, 25).0, [0, 0, 0, 0]);
+    assert_eq!(image.get_pixel(225, 25).0, [0, 0, 0, 0]);
+    assert_eq!(image.get_pixel(219, 19).0, [200, 100, 50, 255]);
+    assert_eq!(image.get_pixel(275, 25).0, [200, 0, 0, 255]);
+    assert_eq!(image.get_pixel(325, 25).0, [0, 0, 0, 0]);
+    assert_eq!(image.get_pixel(375, 25).0, [100, 100, 0, 255]);
+}
+
+#[test]
+fn clipping() {
+    struct ClippingTest;
+
+    impl Sketch for ClippingTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+
+            p5.push();
+            p5.begin_clip(false);
+            p5.begin_shape(ShapeKind::Polygon);
+            for i in 0..10 {
+                let r = if i % 2 == 0 { 90. } else { 40. };
+                let angle = i as f32 * std::f32::consts::PI / 5.;
+                p5.vertex(100. + r * angle.sin(), 100. - r * angle.cos());
+            }
+            p5.end_shape(EndMode::Close);
+            p5.end_clip();
+            p5.stroke((200., 0., 100.));
+            p5.stroke_weight(6.);
+            for i in 0..20 {
+                let x = i as f32 * 12.;
+                p5.line(x, 0., x - 60., 200.);
+            }
+            p5.stroke_weight(10.);
+            p5.stroke((0., 0., 200.));
+            p5.points(&[(100., 100.), (100., 20.), (30., 100.)]);
+            p5.pop();
+
+            // Shapes inside of an inverted clip are cut out, even where they overlap.
+            p5.push();
+            p5.clip(
+                |p5| {
+                    p5.circle(270., 100., 100.);
+                    p5.circle(330., 100., 100.);
+                },
+                true,
+            );
+            p5.no_stroke();
+            p5.fill_gradient(
+                &Gradient::linear(200., 0., 400., 0.)
+                    .color_stop(0., (0., 150., 0.))
+                    .color_stop(1., (250., 200., 0.)),
+            );
+            p5.rect(220., 40., 160., 120., None);
+            p5.pop();
+
+            // Clips are transformed, and nested clips only allow drawing where they overlap.
+            p5.push();
+            p5.translate(100., 300.);
+            p5.rotate(std::f32::consts::FRAC_PI_4);
+            p5.clip(|p5| p5.rect(-60., -60., 120., 120., None), false);
+            p5.reset_matrix();
+            p5.clip(|p5| p5.circle(160., 300., 120.), false);
+            p5.background((250., 120., 0.));
+            p5.blend_mode(BlendMode::SoftLight);
+            p5.fill((40., 40., 220.));
+            p5.circle(100., 300., 100.);
+            p5.pop();
+
+            p5.fill((0., 200., 200.));
+            p5.circle(300., 300., 60.);
+        }
+    }
+
+    assert_snapshot(&mut ClippingTest, 1, snapshot("clipping"), 2);
+
+    struct ClipPixelsTest;
+
+    impl Sketch for ClipPixelsTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.no_stroke();
+
+            p5.push();
+            p5.begin_clip(false);
+            p5.rect(0., 0., 100., 100., None);
+            // Lines and points don't add anything to the clip, and aren't drawn.
+            p5.stroke(255);
+            p5.line(0., 120., 400., 120.);
+            p5.point(200., 50.);
+            p5.no_stroke();
+            p5.end_clip();
+            p5.fill((255., 0., 0.));
+            p5.rect(0., 0., 400., 400., None);
+
+            p5.push();
+            p5.clip(|p5| p5.rect(50., 50., 100., 100., None), false);
+            p5.background((0., 255., 0.));
+            p5.pop();
+
+            p5.fill((0., 0., 255.));
+            p5.rect(0., 0., 60., 400., None);
+            p5.pop();
+
+            p5.push();
+            p5.translate(200., 200.);
+            p5.clip(|p5| p5.rect(0., 0., 50., 50., None), true);
+            p5.fill(255);
+            p5.rect(-50., -50., 150., 150., None);
+            p5.pop();
+
+            p5.fill((255., 255., 0.));
+            p5.rect(300., 300., 50., 50., None);
+
+            // Replacing the canvas only replaces it inside of the clip.
+            p5.push();
+            p5.clip(|p5| p5.rect(300., 0., 100., 100., None), false);
+            p5.blend_mode(BlendMode::Replace);
+            p5.fill((255., 0., 255., 128.));
+            p5.rect(250., 0., 150., 50., None);
+            p5.pop();
+
+            // An unmatched `end_clip` should only print a warning.
+            p5.end_clip();
+        }
+    }
+
+    let p5: P5 = ClipPixelsTest.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(25, 25).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(55, 75).0, [0, 0, 255, 255]);
+    let [r, g, b, _] = image.get_pixel(75, 75).0;
+    assert!(r == 0 && g >= 254 && b == 0);
+    assert_eq!(image.get_pixel(90, 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(125, 75).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(120, 120).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(30, 200).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(200, 120).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(200, 50).0, [0, 0, 0, 255]);
+
+    assert_eq!(image.get_pixel(225, 225).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(175, 175).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(275, 275).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(325, 325).0, [255, 255, 0, 255]);
+    let [r, g, b, a] = image.get_pixel(350, 25).0;
+    assert!(r >= 253 && g == 0 && b >= 253 && a == 128);
+    assert_eq!(image.get_pixel(275, 25).0, [0, 0, 0, 255]);
+
+    // Clips are removed at the start of each frame, so popping a style that was pushed in an
+    // earlier frame removes every clip applied in the current one.
+    struct ClipFramesTest;
+
+    impl Sketch for ClipFramesTest {
+        fn draw(&mut self, p5: &mut P5) {
+            if p5.frame_count() == 1 {
+                p5.clip(|p5| p5.rect(0., 0., 200., 400., None), false);
+                p5.push();
+            } else {
+                p5.clip(|p5| p5.rect(0., 0., 10., 10., None), false);
+                p5.pop();
+                p5.background((255., 0., 0.));
+            }
+        }
+    }
+
+    let p5: P5 = ClipFramesTest.run_headless(2);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(100, 100).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(300, 300).0, [255, 0, 0, 255]);
+
+    // A clip covers every one of its shapes, even where shapes going around in opposite
+    // directions overlap.
+    struct ClipWindingTest;
+
+    impl Sketch for ClipWindingTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.no_stroke();
+            p5.begin_clip(false);
+            // Rects go clockwise, and this polygon goes counter-clockwise.
+            p5.rect(0., 0., 200., 200., None);
+            p5.begin_shape(ShapeKind::Polygon);
+            p5.vertex(100., 100.);
+            p5.vertex(100., 300.);
+            p5.vertex(300., 300.);
+            p5.vertex(300., 100.);
+            p5.end_shape(EndMode::Close);
+            p5.end_clip();
+            p5.background(255);
+        }
+    }
+
+    let p5: P5 = ClipWindingTest.run_headless(0);
+    let image = p5.to_image();
+    let brightness = |x, y| image.get_pixel(x, y).0[0];
+    assert!(brightness(50, 50) >= 254);
+    assert!(brightness(150, 150) >= 254);
+    assert!(brightness(250, 250) >= 254);
+    assert_eq!(brightness(250, 50), 0);
+}
+
+/// Creates a small image with red, green and blue pixels along the top, and a white pixel that's
+/// half transparent in the bottom-right corner.
+fn test_image() -> P5Image {
+    let mut img = image::RgbaImage::from_pixel(3, 2, image::Rgba([255, 255, 0, 255]));
+    img.put_pixel(0, 0, image::Rgba([255, 0, 0, 255]));
+    img.put_pixel(1, 0, image::Rgba([0, 255, 0, 255]));
+    img.put_pixel(2, 0, image::Rgba([0, 0, 255, 255]));
+    img.put_pixel(2, 1, image::Rgba([255, 255, 255, 128]));
+    P5Image::from(img)
+}
+
+#[test]
+fn images() {
+    struct ImagesTest {
+        img: P5Image,
+    }
+
+    impl Sketch for ImagesTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(220);
+            p5.image(&self.img, 20., 20., 90., 60.);
+            p5.no_smooth();
+            p5.image(&self.img, 130., 20., 90., 60.);
+            p5.image_region(&self.img, 240., 20., 60., 60., 1., 0., 2., 2.);
+            p5.smooth();
+
+            p5.push();
+            p5.image_mode(ImageMode::Center);
+            p5.translate(100., 200.);
+            p5.rotate(0.5);
+            p5.image(&self.img, 0., 0., 150., 100.);
+            p5.pop();
+
+            p5.push();
+            p5.image_mode(ImageMode::Corners);
+            p5.begin_clip(false);
+            p5.circle(300., 200., 120.);
+            p5.end_clip();
+            p5.blend_mode(BlendMode::Multiply);
+            p5.image(&self.img, 360., 260., 240., 140.);
+            p5.pop();
+
+            p5.no_smooth();
+            p5.image(&self.img, 20., 300., 360., 80.);
+        }
+    }
+
+    let mut sketch = ImagesTest { img: test_image() };
+    assert_snapshot(&mut sketch, 1, snapshot("images"), 2);
+
+    struct ImagePixelsTest {
+        img: P5Image,
+    }
+
+    impl Sketch for ImagePixelsTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.no_smooth();
+            p5.image(&self.img, 0., 0., 150., 100.);
+
+            p5.image_mode(ImageMode::Center);
+            p5.image(&self.img, 225., 50., 150., 100.);
+            p5.image_mode(ImageMode::Corners);
+            p5.image(&self.img, 150., 200., 0., 100.);
+
+            // Only the part of the source rectangle that's inside the image is drawn.
+            p5.image_mode(ImageMode::Corner);
+            p5.image_region(&self.img, 300., 0., 100., 50., 2., 0., 2., 1.);
+            p5.image_region(&self.img, 300., 50., 100., 50., 2., 1., 2., 1.);
+
+            p5.smooth();
+            p5.image(&self.img, 150., 200., 150., 100.);
+        }
+    }
+
+    let p5: P5 = ImagePixelsTest { img: test_image() }.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(25, 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(125, 25).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(125, 75).0, [128, 128, 128, 255]);
+    assert_eq!(image.get_pixel(175, 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(275, 75).0, [128, 128, 128, 255]);
+    assert_eq!(image.get_pixel(25, 175).0, [255, 255, 0, 255]);
+    assert_eq!(image.get_pixel(125, 125).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(325, 25).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(375, 25).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(325, 75).0, [128, 128, 128, 255]);
+    assert_eq!(image.get_pixel(375, 75).0, [0, 0, 0, 255]);
+    // Smoothed images blend between their pixels.
+    let [r, g, b, _] = image.get_pixel(250, 250).0;
+    assert!(r > 0 && g > 0 && b > 0, "{:?}", [r, g, b]);
+
+    let path = std::env::temp_dir().join("p5-rs-load-image.png");
+    let mut img = image::RgbaImage::new(3, 2);
+    img.put_pixel(2, 1, image::Rgba([255, 255, 255, 128]));
+    img.save(&path).unwrap();
+    let loaded = p5.load_image(&path).unwrap();
+    assert_eq!((loaded.width(), loaded.height()), (3, 2));
+    assert_eq!(loaded, P5Image::from(img));
+    assert!(p5.load_image("tests/snapshots/missing.png").is_err());
+}
+
+#[test]
+fn tint() {
+    struct TintTest {
+        img: P5Image,
+    }
+
+    impl Sketch for TintTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background((0., 0., 200.));
+            p5.no_smooth();
+            p5.tint((255., 0., 0.));
+            p5.image(&self.img, 0., 0., 150., 100.);
+
+            p5.push();
+            p5.tint((255., 255., 255., 128.));
+            p5.image(&self.img, 150., 0., 150., 100.);
+            p5.color_mode(HSB);
+            p5.tint((240., 100., 100.));
+            p5.image(&self.img, 0., 100., 150., 100.);
+            p5.pop();
+
+            // The tint is restored by `pop`, and removed by `no_tint`.
+            p5.image(&self.img, 150., 100., 150., 100.);
+            p5.no_tint();
+            p5.image(&self.img, 0., 200., 150., 100.);
+        }
+    }
+
+    let p5: P5 = TintTest { img: test_image() }.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(25, 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(75, 25).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(25, 75).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(175, 25).0, [128, 0, 100, 255]);
+    assert_eq!(image.get_pixel(275, 25).0, [0, 0, 228, 255]);
+    assert_eq!(image.get_pixel(25, 125).0, [0, 0, 0, 255]);
+    assert_eq!(image.get_pixel(125, 125).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(175, 175).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(75, 225).0, [0, 255, 0, 255]);
+}
+
+#[test]
+fn pixels() {
+    struct PixelsTest;
+
+    impl Sketch for PixelsTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background((0., 0., 255.));
+            p5.no_stroke();
+            p5.fill((255., 0., 0., 128.));
+            p5.rect(0., 0., 100., 100., None);
+            assert!(p5.pixels_mut().is_empty());
+
+            p5.load_pixels();
+            let width = p5.to_image().width() as usize;
+            let pixels = p5.pixels_mut();
+            assert_eq!(&pixels[..4], &[128, 0, 127, 255]);
+            for pixel in pixels[..4 * width * 50].chunks_exact_mut(4) {
+                pixel.copy_from_slice(&[0, 255, 0, 128]);
+            }
+            p5.update_pixels();
+
+            assert_eq!(p5.get(10., 10.), Color::new(0, 255, 0, 128));
+            assert_eq!(p5.get(10.9, 60.2), Color::new(128, 0, 127, 255));
+            assert_eq!(p5.get(-1., 10.), Color::new(0, 0, 0, 0));
+
+            // `set` only changes the canvas once `update_pixels` is called.
+            p5.set(200., 200., (255., 255., 0.));
+            assert_eq!(p5.get(200., 200.), Color::new(0, 0, 255, 255));
+            p5.color_mode(HSB);
+            p5.set(201., 200., (120., 100., 100.));
+            p5.set(-5., 200., (0., 0., 0.));
+            p5.update_pixels();
+            assert_eq!(p5.get(200., 200.), Color::new(255, 255, 0, 255));
+            assert_eq!(p5.get(201., 200.), Color::new(0, 255, 0, 255));
+
+            let region = p5.get_region(199., 199., 3., 2.);
+            assert_eq!((region.width(), region.height()), (3, 2));
+            let mut expected = image::RgbaImage::from_pixel(3, 2, image::Rgba([0, 0, 255, 255]));
+            expected.put_pixel(1, 1, image::Rgba([255, 255, 0, 255]));
+            expected.put_pixel(2, 1, image::Rgba([0, 255, 0, 255]));
+            assert_eq!(region, P5Image::from(expected));
+
+            // The parts of the region outside of the canvas are transparent.
+            let region = p5.get_region(-1., -1., 2., 2.);
+            let mut expected = image::RgbaImage::new(2, 2);
+            expected.put_pixel(1, 1, image::Rgba([0, 255, 0, 128]));
+            assert_eq!(region, P5Image::from(expected));
+        }
+    }
+
+    PixelsTest.run_headless(1);
 }
 
 #[test]
@@ -215,7 +1217,7 @@ fn matrix() {
         }
     }
 
-    MatrixTest.run();
+    assert_snapshot(&mut MatrixTest, 60, snapshot("matrix"), 2);
 }
 
 #[test]
@@ -255,7 +1257,7 @@ fn transforms() {
         }
     }
 
-    TransformsTest.run();
+    assert_snapshot(&mut TransformsTest, 60, snapshot("transforms"), 2);
 }
 
 #[test]
@@ -304,7 +1306,7 @@ fn colors() {
         }
     }
 
-    ColorsTest.run();
+    assert_snapshot(&mut ColorsTest, 1, snapshot("colors"), 2);
 }
 
 #[test]
@@ -325,5 +1327,358 @@ fn text() {
         }
     }
 
-    TextTest.run();
+    TextTest.run_headless(1);
+}
+
+#[test]
+fn text_transform() {
+    struct TextTransformTest {
+        translate: bool,
+    }
+
+    impl Sketch for TextTransformTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(255);
+            p5.fill(0);
+            p5.text_size(40.);
+            if self.translate {
+                p5.translate(100., 50.);
+                p5.text("p5-rs", 10., 100.);
+            } else {
+                p5.text("p5-rs", 110., 150.);
+            }
+        }
+    }
+
+    // Text is moved by the current transformation, like every other shape.
+    let translated = TextTransformTest { translate: true }.run_headless(0);
+    let moved = TextTransformTest { translate: false }.run_headless(0);
+    let (translated, moved) = (translated.to_image(), moved.to_image());
+    assert!(moved.pixels().any(|pixel| pixel.0[0] < 128));
+    let differences = translated
+        .pixels()
+        .zip(moved.pixels())
+        .filter(|(a, b)| a.0[0].abs_diff(b.0[0]) > 2)
+        .count();
+    assert_eq!(differences, 0);
+}
+
+#[test]
+fn save_canvas() {
+    struct SaveCanvasTest;
+
+    impl Sketch for SaveCanvasTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background((255., 0., 0.));
+            p5.no_stroke();
+            p5.fill((0., 0., 255., 127.));
+            p5.rect(0., 0., 200., 200., None);
+        }
+    }
+
+    let p5 = SaveCanvasTest.run_headless(1);
+    let path = std::env::temp_dir().join("p5-rs-save-canvas.png");
+    p5.save_canvas(&path).unwrap();
+
+    let saved = image::open(&path).unwrap().to_rgba8();
+    assert_eq!(saved.dimensions(), (400, 400));
+    assert_eq!(saved.get_pixel(300, 300).0, [255, 0, 0, 255]);
+
+    let blended = saved.get_pixel(100, 100).0;
+    assert!(blended[0] > 120 && blended[0] < 135);
+    assert!(blended[2] > 120 && blended[2] < 135);
+}
+
+#[test]
+fn save_frames() {
+    struct SaveFramesTest;
+
+    impl Sketch for SaveFramesTest {
+        fn draw(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.rect(p5.frame_count as f32 * 10., 0., 10., 10., None);
+        }
+    }
+
+    let dir = std::env::temp_dir().join("p5-rs-save-frames");
+    SaveFramesTest.save_frames(&dir, "frame", 3).unwrap();
+
+    for frame in 1..=3u32 {
+        let saved = image::open(dir.join(format!("frame-{:05}.png", frame)))
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(saved.get_pixel(frame * 10 + 5, 5).0, [255, 255, 255, 255]);
+    }
+}
+
+#[test]
+fn save_gif() {
+    use image::AnimationDecoder;
+
+    struct SaveGifTest(f32);
+
+    impl Sketch for SaveGifTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.frame_rate(self.0);
+        }
+
+        fn draw(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.circle(p5.frame_count as f32 * 50., 200., 40.);
+        }
+    }
+
+    let path = std::env::temp_dir().join("p5-rs-save-gif.gif");
+    let decode = |path: &std::path::Path| {
+        let file = std::fs::File::open(path).unwrap();
+        image::codecs::gif::GifDecoder::new(file)
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap()
+    };
+
+    SaveGifTest(20.).save_gif(&path, 4).unwrap();
+    let frames = decode(&path);
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[0].delay().numer_denom_ms(), (50, 1));
+
+    // Frame rates that would pause the sketch fall back to 60 fps.
+    for &fps in &[0., -10., f32::NAN] {
+        SaveGifTest(fps).save_gif(&path, 1).unwrap();
+        assert_eq!(decode(&path)[0].delay().numer_denom_ms(), (10, 1));
+    }
+    // Tiny frame rates get the longest delay a GIF can store.
+    SaveGifTest(1e-40).save_gif(&path, 1).unwrap();
+    assert_eq!(decode(&path)[0].delay().numer_denom_ms(), (655350, 1));
+}
+
+#[test]
+fn generic_sketch() {
+    struct GenericTest;
+
+    impl<B: Backend> Sketch<B> for GenericTest {
+        fn draw(&mut self, p5: &mut B) {
+            p5.background(0);
+            p5.no_stroke();
+            p5.rect(p5.frame_count() as f32 * 10., 0., 10., 10., None);
+        }
+    }
+
+    let p5: P5 = GenericTest.run_headless(2);
+    assert_eq!(p5.frame_count(), 2);
+
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(25, 5).0, [255, 255, 255, 255]);
+    assert_eq!(image.get_pixel(15, 5).0, [0, 0, 0, 255]);
+}
+
+#[test]
+fn svg() {
+    struct SvgTest;
+
+    impl<B: Backend> Sketch<B> for SvgTest {
+        fn setup(&mut self, p5: &mut B) {
+            p5.background(220);
+            p5.fill((255., 0., 0.));
+            p5.rect(10., 20., 30., 40., RectRounding::equal(5.));
+
+            p5.no_fill();
+            p5.stroke_weight(2.);
+            p5.translate(100., 100.);
+            p5.line(0., 0., 50., 50.);
+            p5.stroke_cap(StrokeCap::Round);
+            p5.stroke_join(StrokeJoin::Bevel);
+            p5.stroke_dash(&[5., 3., 1.], 2.);
+            p5.ellipse(0., 0., 20., 10.);
+
+            p5.fill((0., 0., 255., 127.));
+            p5.text("<p5-rs & SVG>", 10., 200.);
+        }
+    }
+
+    let p5: SvgP5 = SvgTest.run_headless(1);
+    let path = std::env::temp_dir().join("p5-rs-svg.svg");
+    p5.save_canvas(&path).unwrap();
+
+    let svg = std::fs::read_to_string(&path).unwrap();
+    assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="400""#));
+    assert!(svg.contains(r##"<path d="M0 0L400 0L400 400L0 400Z" fill="#dcdcdc"/>"##));
+    assert!(svg.contains(r##"fill="#ff0000" stroke="#000000" stroke-width="1""##));
+    assert!(svg.contains(r#"<path d="M100 100L150 150" fill="none""#));
+    assert!(svg.contains(r#"stroke-linecap="round" stroke-linejoin="bevel" stroke-dasharray="5 3 1 5 3 1" stroke-dashoffset="2""#));
+    assert_eq!(svg.matches("<path").count(), 4);
+    assert!(svg.contains(r#"fill-opacity="0.49803922">&lt;p5-rs &amp; SVG&gt;</text>"#));
+    assert!(svg.trim_end().ends_with("</svg>"));
+}
+
+#[test]
+fn pdf() {
+    struct PdfTest;
+
+    impl<B: Backend> Sketch<B> for PdfTest {
+        fn setup(&mut self, p5: &mut B) {
+            p5.text_size(20.);
+        }
+
+        fn draw(&mut self, p5: &mut B) {
+            p5.background(220);
+            p5.fill((255., 0., 0., 127.));
+            p5.ellipse(200., 200., 100., 50.);
+            p5.text("(p5-rs)", 10., 30.);
+        }
+    }
+
+    let p5: PdfP5 = PdfTest.run_headless(3);
+    let path = std::env::temp_dir().join("p5-rs-pdf.pdf");
+    p5.save_canvas(&path).unwrap();
+
+    let bytes = std::fs::read(&path).unwrap();
+    let pdf = String::from_utf8_lossy(&bytes);
+    assert!(pdf.starts_with("%PDF-1."));
+    assert!(pdf.contains("/Type /Pages"));
+    assert!(pdf.contains("/Count 3"));
+    assert_eq!(pdf.matches("/Type /Page ").count(), 3);
+    assert!(pdf.contains("/MediaBox [0 0 400 400]"));
+    assert!(pdf.contains("0 0 m\n400 0 l\n400 400 l\n0 400 l\nh\nf\n"));
+    assert!(pdf.contains("/GS0 << /ca 0.49803922 /CA 1 >>"));
+    // The default font is embedded as TrueType or OpenType, depending on its outlines.
+    let (dict, format) = if pdf.contains("/FontFile2") {
+        ("/Length1", "/Subtype /TrueType")
+    } else {
+        ("/Subtype /OpenType", "/Subtype /Type1")
+    };
+    assert!(pdf.contains(format));
+    // The embedded font can be loaded again, and has the glyphs used by the text.
+    let find = |from: usize, needle: &str| {
+        let needle = needle.as_bytes();
+        from + bytes[from..]
+            .windows(needle.len())
+            .position(|w| w == needle)
+            .unwrap()
+    };
+    let start = find(find(0, dict), "stream\n") + "stream\n".len();
+    let end = find(start, "\nendstream");
+    let font = font_kit::font::Font::from_bytes(bytes[start..end].to_vec().into(), 0).unwrap();
+    assert!(font.glyph_for_char('p').is_some());
+    assert!(pdf.contains(r"(\(p5-rs\)) Tj"));
+    assert!(pdf.trim_end().ends_with("%%EOF"));
+}
+
+#[test]
+fn pdf_pages() {
+    struct PagesTest;
+
+    impl Sketch<PdfP5> for PagesTest {
+        fn setup(&mut self, p5: &mut PdfP5) {
+            p5.background(220);
+        }
+
+        fn draw(&mut self, p5: &mut PdfP5) {
+            p5.background(220);
+            p5.rect(10., 10., 50., 50., None);
+            p5.add_page();
+            p5.circle(200., 200., 100.);
+            p5.add_page();
+        }
+    }
+
+    let page_count = |p5: &PdfP5, name: &str| {
+        let path = std::env::temp_dir().join(name);
+        p5.save_canvas(&path).unwrap();
+        let pdf = String::from_utf8_lossy(&std::fs::read(&path).unwrap()).into_owned();
+        pdf.matches("/Type /Page ").count()
+    };
+
+    // A document drawn in `setup` is saved as a single page.
+    let p5: PdfP5 = PagesTest.run_headless(0);
+    assert_eq!(page_count(&p5, "p5-rs-pdf-setup.pdf"), 1);
+
+    // Since nothing is drawn after the last `add_page` of each frame, the end of the frame and
+    // `save_canvas` don't add it again.
+    let p5: PdfP5 = PagesTest.run_headless(2);
+    assert_eq!(page_count(&p5, "p5-rs-pdf-pages.pdf"), 4);
+}
+
+#[test]
+fn push_pop() {
+    struct PushPopTest;
+
+    impl Sketch for PushPopTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.background(0);
+            p5.no_stroke();
+            p5.fill((255., 0., 0.));
+
+            p5.push();
+            p5.fill((0., 0., 255.));
+            p5.translate(200., 0.);
+            p5.rect(0., 0., 50., 50., None);
+            p5.pop();
+
+            p5.rect(0., 0., 50., 50., None);
+
+            // An unbalanced `pop` should only print a warning.
+            p5.pop();
+            p5.rect(100., 0., 50., 50., None);
+        }
+    }
+
+    let p5: P5 = PushPopTest.run_headless(0);
+    let image = p5.to_image();
+    assert_eq!(image.get_pixel(225, 25).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(25, 25).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(125, 25).0, [255, 0, 0, 255]);
+}
+
+#[test]
+fn angle_mode() {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, FRAC_PI_6};
+
+    struct AngleModeTest(AngleMode);
+
+    impl Sketch for AngleModeTest {
+        fn setup(&mut self, p5: &mut P5) {
+            p5.angle_mode(self.0);
+            let angle = |degrees: f32| match self.0 {
+                AngleMode::Radians => degrees.to_radians(),
+                AngleMode::Degrees => degrees,
+            };
+
+            p5.background(220);
+            p5.translate(100., 100.);
+            p5.rotate(angle(30.));
+            p5.rect(0., 0., 50., 50., None);
+            p5.shear_x(angle(20.));
+            p5.shear_y(angle(10.));
+            p5.rect(100., 0., 50., 50., None);
+            p5.arc(0., 100., 50., 50., angle(45.), angle(270.), ArcMode::Pie);
+            p5.fill_gradient(
+                &Gradient::conic(0., 0., angle(60.))
+                    .color_stop(0., (255., 0., 0.))
+                    .color_stop(1., (0., 0., 255.)),
+            );
+            p5.circle(200., 0., 80.);
+        }
+    }
+
+    let radians: P5 = AngleModeTest(AngleMode::Radians).run_headless(0);
+    let degrees: P5 = AngleModeTest(AngleMode::Degrees).run_headless(0);
+    assert!(radians.to_image() == degrees.to_image());
+
+    let mut p5 = degrees;
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-5;
+    assert!(close(p5.sin(30.), 0.5));
+    assert!(close(p5.cos(60.), 0.5));
+    assert!(close(p5.tan(45.), 1.));
+    assert!(close(p5.asin(0.5), 30.));
+    assert!(close(p5.acos(0.5), 60.));
+    assert!(close(p5.atan(1.), 45.));
+    assert!(close(p5.atan2(1., 0.), 90.));
+    assert!(close(p5.radians(90.), FRAC_PI_2));
+    assert!(close(p5.degrees(FRAC_PI_2), 90.));
+
+    p5.angle_mode(AngleMode::Radians);
+    assert!(close(p5.sin(FRAC_PI_6), 0.5));
+    assert!(close(p5.atan(1.), FRAC_PI_4));
 }